@@ -0,0 +1,149 @@
+use prime::calculator::*;
+use prime::generator::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+const UNTIL: u32 = 20_000_000;
+
+/// Checks the candidates like the parallel generators do, measuring how long every thread is busy.
+fn utilization(known_primes: &[u32], start: u32, max_len: usize) -> f64 {
+    let threads = rayon::current_num_threads();
+    let busy: Vec<AtomicU64> = (0..threads).map(|_| AtomicU64::new(0)).collect();
+
+    let wall = Instant::now();
+    u32::par_filter_odd(start | 1, UNTIL, max_len, |n| {
+        let t = Instant::now();
+        let prime = is_prime(n, known_primes);
+        busy[rayon::current_thread_index().unwrap()]
+            .fetch_add(t.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        prime
+    });
+    let wall = wall.elapsed().as_nanos() as f64;
+
+    let busy: u64 = busy.iter().map(|b| b.load(Ordering::Relaxed)).sum();
+    busy as f64 / (wall * threads as f64)
+}
+
+/// Compares the sieve to parallel trial division at several sizes, to check [`SIEVE_LIMIT`].
+///
+/// Run it with the `simd` feature on a nightly compiler to time the vectorized sieve instead.
+fn sieve_vs_trial_division() {
+    for until in [1_000u64, 1_000_000, 100_000_000, 1 << 27] {
+        let start = Instant::now();
+        let mut bits = vec![0; prime::sieve::bits_required(until as usize)];
+        prime::sieve::sieve_into_bits(until as usize, &mut bits);
+        let sieve = start.elapsed();
+
+        let start = Instant::now();
+        par_prime_generator(until, Vec::new(), 0);
+        println!(
+            "{:<28} {:>10.2?} sieve, {:>10.2?} trial division",
+            format!("until {}", until),
+            sieve,
+            start.elapsed()
+        );
+    }
+}
+
+/// Times the segmented sieve until 10^9 with several window sizes, to check [`prime::sieve::DEFAULT_SEGMENT_BYTES`].
+fn segment_sizes() {
+    for segment_bytes in [16 << 10, 64 << 10, 256 << 10, 1 << 20, 4 << 20, 16 << 20] {
+        let start = Instant::now();
+        let mut count = 0;
+        prime::sieve::segmented_sieve_map(1_000_000_000, segment_bytes, |primes| {
+            count += primes.len()
+        });
+        assert_eq!(count, 50_847_534);
+        println!(
+            "{:<28} {:>10.2?}",
+            format!("segment of {} KiB", segment_bytes >> 10),
+            start.elapsed()
+        );
+    }
+}
+
+/// Times trial division of big candidates with and without prefetching, with a list of divisors bigger than the cache, to check [`PREFETCH_DISTANCE`].
+#[cfg(feature = "prefetch")]
+fn prefetch_distances() {
+    let known_primes = best_primes_below(100_000_000u64, Vec::new());
+    // Primes near 10^16 need the whole list.
+    let candidates: Vec<u64> = (10_000_000_000_000_001..)
+        .step_by(2)
+        .filter(|&n| deterministic_is_prime(n))
+        .take(100)
+        .collect();
+
+    let start = Instant::now();
+    assert!(candidates.iter().all(|&n| is_prime(n, &known_primes)));
+    println!("{:<28} {:>10.2?}", "without prefetching", start.elapsed());
+
+    for distance in [4, 8, PREFETCH_DISTANCE, 64, 256] {
+        let start = Instant::now();
+        assert!(candidates
+            .iter()
+            .all(|&n| is_prime_prefetch(n, &known_primes, distance)));
+        println!(
+            "{:<28} {:>10.2?}",
+            format!("prefetch distance {}", distance),
+            start.elapsed()
+        );
+    }
+}
+
+fn main() {
+    println!(
+        "Generating primes until {} with {} threads...",
+        UNTIL,
+        rayon::current_num_threads()
+    );
+
+    let start = Instant::now();
+    let expected = prime_generator(UNTIL, Vec::new(), 0);
+    println!("{:<28} {:>10.2?}", "prime_generator", start.elapsed());
+
+    let start = Instant::now();
+    assert_eq!(wheel_generator(UNTIL, Vec::new(), 0), expected);
+    println!("{:<28} {:>10.2?}", "wheel_generator", start.elapsed());
+
+    let start = Instant::now();
+    assert_eq!(par_prime_generator(UNTIL, Vec::new(), 0), expected);
+    println!("{:<28} {:>10.2?}", "par_prime_generator", start.elapsed());
+
+    let start = Instant::now();
+    assert_eq!(best_primes_below(UNTIL, Vec::new()), expected);
+    println!("{:<28} {:>10.2?}", "best_primes_below", start.elapsed());
+
+    for max_len in [64, 1024, 16384] {
+        let start = Instant::now();
+        assert_eq!(
+            par_prime_generator_balanced(UNTIL, Vec::new(), 0, max_len),
+            expected
+        );
+        println!(
+            "{:<28} {:>10.2?}",
+            format!("balanced, max_len {}", max_len),
+            start.elapsed()
+        );
+    }
+
+    // The candidates span from the square root of UNTIL to UNTIL, several orders of magnitude.
+    let sqr = (UNTIL as f64).sqrt() as u32 + 1;
+    let known_primes = prime_generator(sqr, Vec::new(), 0);
+    for max_len in [usize::MAX, 64, 1024, 16384] {
+        let label = if max_len == usize::MAX {
+            "default".to_string()
+        } else {
+            max_len.to_string()
+        };
+        println!(
+            "{:<28} {:>9.1}%",
+            format!("utilization, max_len {}", label),
+            utilization(&known_primes, sqr, max_len) * 100.0
+        );
+    }
+
+    sieve_vs_trial_division();
+    segment_sizes();
+    #[cfg(feature = "prefetch")]
+    prefetch_distances();
+}
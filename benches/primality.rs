@@ -0,0 +1,117 @@
+use num::integer::Roots;
+use prime::calculator::*;
+use std::hint::black_box;
+use std::time::Instant;
+
+fn random_numbers(count: usize) -> Vec<u64> {
+    // xorshift64, so that every run tests the same numbers.
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    (0..count)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+fn bench<F: Fn(u64) -> bool>(name: &str, numbers: &[u64], is_prime: F) -> Vec<bool> {
+    let start = Instant::now();
+    let results: Vec<bool> = numbers.iter().map(|&n| is_prime(black_box(n))).collect();
+    println!(
+        "{:<24} {:>10.2?} ({} primes)",
+        name,
+        start.elapsed(),
+        results.iter().filter(|&&p| p).count()
+    );
+    results
+}
+
+/// The loop [`is_prime`] used before the wheel check, to measure what the check saves.
+fn is_prime_without_wheel(n: u64, known_primes: &[u64]) -> bool {
+    if n == 0 || n == 1 {
+        return false;
+    }
+    let sqr = n.sqrt();
+    known_primes
+        .iter()
+        .take_while(|&&p| p <= sqr)
+        .all(|&p| !n.is_multiple_of(p) || n == p)
+}
+
+/// [`is_prime_without_wheel`], but rejecting the numbers that aren't 6k ± 1 first.
+fn is_prime_6k(n: u64, known_primes: &[u64]) -> bool {
+    if n <= 3 {
+        return n >= 2;
+    }
+    if n % 6 != 1 && n % 6 != 5 {
+        return false;
+    }
+    is_prime_without_wheel(n, known_primes)
+}
+
+fn main() {
+    let numbers = random_numbers(1_000_000);
+    println!("Testing {} random u64...", numbers.len());
+
+    let deterministic = bench("deterministic_is_prime", &numbers, deterministic_is_prime);
+    let mont = bench("mont_is_prime", &numbers, mont_is_prime);
+    assert_eq!(deterministic, mont);
+
+    let dense: Vec<u64> = (1_000_000..3_000_000).collect();
+    let known_primes = prime::generator::prime_generator(2_000u64, Vec::new(), 0);
+    println!("Testing every number from 1000000 to 3000000...");
+    let without_wheel = bench("is_prime (no wheel)", &dense, |n| {
+        is_prime_without_wheel(n, &known_primes)
+    });
+    let with_6k = bench("is_prime (6k ± 1)", &dense, |n| {
+        is_prime_6k(n, &known_primes)
+    });
+    let with_wheel = bench("is_prime", &dense, |n| is_prime(n, &known_primes));
+    let divisors = FastDivisors::new(&known_primes);
+    let fast = bench("is_prime_fast", &dense, |n| is_prime_fast(n, &divisors));
+    assert_eq!(without_wheel, with_6k);
+    assert_eq!(without_wheel, with_wheel);
+    assert_eq!(without_wheel, fast);
+
+    // How many numbers reach the divisor loop with each guard.
+    let reaching_6k = dense.iter().filter(|&&n| n % 6 == 1 || n % 6 == 5).count();
+    let reaching_wheel = dense
+        .iter()
+        .filter(|&&n| n % 2 != 0 && n % 3 != 0 && n % 5 != 0)
+        .count();
+    for (label, reaching) in [
+        ("no guard", dense.len()),
+        ("6k ± 1", reaching_6k),
+        ("wheel of 30", reaching_wheel),
+    ] {
+        println!(
+            "{:<24} {:>9.1}% reach the divisor loop",
+            label,
+            reaching as f64 / dense.len() as f64 * 100.0
+        );
+    }
+
+    // Primes near 10^12 and products of two primes near 10^6, the worst cases of trial division.
+    let primes: Vec<u64> = (1_000_000_000_000..)
+        .filter(|&n| deterministic_is_prime(n))
+        .take(100)
+        .collect();
+    let factors: Vec<u64> = (1_000_000..)
+        .filter(|&n| deterministic_is_prime(n))
+        .take(11)
+        .collect();
+    let semiprimes: Vec<u64> = factors
+        .iter()
+        .flat_map(|&p| factors.iter().map(move |&q| p * q))
+        .collect();
+    for (label, numbers) in [("primes", primes), ("semiprimes", semiprimes)] {
+        println!("Testing {} {} near 10^12...", numbers.len(), label);
+        let trial = bench("trial division", &numbers, |n| {
+            ignorant_is_prime_cutoff(n, &[], u64::MAX)
+        });
+        let hybrid = bench("ignorant_is_prime", &numbers, |n| ignorant_is_prime(n, &[]));
+        assert_eq!(trial, hybrid);
+    }
+}
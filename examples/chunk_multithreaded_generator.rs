@@ -20,11 +20,10 @@ fn main() {
     let (tx, rx) = unbounded();
 
     thread::spawn(move || {
-        par_prime_generator_map_chunks(
+        par_prime_generator_map_chunks_with(
             max,
             Vec::new(),
-            chunk_size,
-            0,
+            ChunkOptions::default().chunk_size(chunk_size),
             |_, _| true,
             |arr| {
                 arr.iter().for_each(|&n| tx.send(n).unwrap());
@@ -0,0 +1,34 @@
+use prime::io::read_record;
+use std::io::{self, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+/// Asks the server for every prime less than until, decoding the records as they arrive.
+pub fn request_primes<A: ToSocketAddrs>(address: A, until: u64) -> io::Result<Vec<u64>> {
+    let mut stream = TcpStream::connect(address)?;
+    writeln!(stream, "{}", until)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut primes = Vec::new();
+    while let Some((mut chunk, _)) = read_record::<u64, _>(&mut reader)? {
+        primes.append(&mut chunk);
+    }
+    Ok(primes)
+}
+
+fn main() -> io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_string());
+    let until: u64 = std::env::args()
+        .nth(2)
+        .map(|s| s.parse().expect("Error parsing the number to arrive to!"))
+        .unwrap_or(1000);
+
+    let primes = request_primes(address, until)?;
+    println!(
+        "Received {} primes, the last is {:?}.",
+        primes.len(),
+        primes.last()
+    );
+    Ok(())
+}
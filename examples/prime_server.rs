@@ -0,0 +1,42 @@
+use prime::io::encoded_stream;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+/// How many numbers are checked for every record sent to the client.
+const CHUNK_SIZE: u64 = 1_000_000;
+
+/// Reads the number the client wants to arrive to, written in decimal on a line, and sends back every prime less than it in the records of `encoded_stream`.
+pub fn handle_client(stream: TcpStream) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new(&stream).read_line(&mut request)?;
+    let until: u64 = request
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let mut stream = stream;
+    for record in encoded_stream(until, CHUNK_SIZE) {
+        stream.write_all(&record)?;
+    }
+    stream.flush()
+}
+
+fn main() -> io::Result<()> {
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_string());
+
+    let listener = TcpListener::bind(&address)?;
+    println!("Serving primes on {}...", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            let peer = stream.peer_addr();
+            if let Err(e) = handle_client(stream) {
+                eprintln!("Error serving {:?}: {}", peer, e);
+            }
+        });
+    }
+    Ok(())
+}
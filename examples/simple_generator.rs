@@ -1,3 +1,4 @@
+use prime::display::format_table;
 use prime::generator::*;
 use std::io;
 
@@ -10,7 +11,6 @@ fn main() {
         .expect("Error reading input!");
     let max: u64 = max.trim().parse().expect("Error parsing input!");
 
-    prime_generator(max, Vec::new(), 0).iter().for_each(|n| {
-        println!("{}", n);
-    });
+    let primes = prime_generator(max, Vec::new(), 0);
+    print!("{}", format_table(&primes, 10));
 }
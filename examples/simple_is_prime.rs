@@ -0,0 +1,19 @@
+use prime::calculator::ignorant_is_prime;
+use std::io;
+
+fn main() {
+    // Asking for the number the user wants to check.
+    println!("Write the number you want to check:");
+    let mut num = String::new();
+    io::stdin()
+        .read_line(&mut num)
+        .expect("Error reading input!");
+    let num: u64 = num.trim().parse().expect("Error parsing input!");
+
+    // No list of primes is needed.
+    if ignorant_is_prime(num, &[]) {
+        println!("{} is prime.", num);
+    } else {
+        println!("{} is not prime.", num);
+    }
+}
@@ -0,0 +1,73 @@
+use prime::calculator::*;
+use prime::format::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() -> ExitCode {
+    let path_arg = std::env::args().nth(1);
+    let path: &str = if let Some(p) = path_arg.as_ref() {
+        p
+    } else {
+        "primes.dat"
+    };
+    let samples: usize = std::env::args()
+        .nth(2)
+        .map(|s| s.parse().expect("Error parsing the number of samples!"))
+        .unwrap_or(1000);
+
+    // Reading the file and checking that the primes are ordered and not divisible by each other.
+    println!("Reading and validating {}...", path);
+    let file = match File::open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("Error opening the file: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (primes, arrived_to) = match read_primes_checked::<u32, _>(&mut BufReader::new(file)) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("The file is corrupted: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!(
+        "Found {} primes, calculated until {}.",
+        primes.len(),
+        arrived_to
+    );
+
+    // Checking some random primes again without trusting the file.
+    println!("Checking {} random primes again...", samples);
+    let mut state = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+        | 1;
+    let mut corrupted = false;
+    for _ in 0..samples.min(primes.len()) {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let index = (state % primes.len() as u64) as usize;
+        if !ignorant_is_prime(primes[index], &[]) {
+            eprintln!(
+                "{} at index {} isn't prime (offset {})",
+                primes[index],
+                index,
+                element_offset::<u32>(index)
+            );
+            corrupted = true;
+        }
+    }
+
+    if corrupted {
+        ExitCode::FAILURE
+    } else {
+        println!("The file is valid!");
+        ExitCode::SUCCESS
+    }
+}
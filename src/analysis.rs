@@ -0,0 +1,691 @@
+use crate::calculator::*;
+use crate::generator::*;
+use num::integer::Roots;
+use num::{CheckedAdd, FromPrimitive, ToPrimitive};
+use rayon::prelude::*;
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+/// e raised to minus the Euler-Mascheroni constant, the limit of [`mertens_third_constant_estimate`].
+pub const E_TO_MINUS_GAMMA: f64 = 0.561_459_483_566_885_2;
+
+/// Calculates the sum of the reciprocals of the primes passed in.
+///
+/// The sum over every prime diverges, but extremely slowly: it grows like ln(ln(n)).
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// assert!((reciprocal_sum(&[2, 3, 5, 7]) - 1.1762).abs() < 1e-4);
+/// ```
+pub fn reciprocal_sum<N: ToPrimitive>(primes: &[N]) -> f64 {
+    primes.iter().map(|p| 1.0 / p.to_f64().unwrap()).sum()
+}
+
+/// Calculates the product of (1 - 1/p) for every prime p less than until, multiplied by ln(until).
+///
+/// By Mertens' third theorem, the result tends to [`E_TO_MINUS_GAMMA`] as until grows.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// assert!((mertens_third_constant_estimate(100_000) - E_TO_MINUS_GAMMA).abs() < 0.01);
+/// ```
+pub fn mertens_third_constant_estimate<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
+>(
+    until: N,
+) -> f64 {
+    let product: f64 = prime_generator(until, Vec::new(), N::zero())
+        .iter()
+        .map(|p| 1.0 - 1.0 / p.to_f64().unwrap())
+        .product();
+    product * until.to_f64().unwrap().ln()
+}
+
+/// Some statistics about the primes less than a number, returned by [`prime_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrimeStats<N> {
+    /// How many primes there are.
+    pub count: usize,
+    /// The biggest of them, if there is any.
+    pub largest: Option<N>,
+    /// The biggest difference between two consecutive primes.
+    pub max_gap: N,
+    /// How many pairs of primes differ by 2.
+    pub twin_count: usize,
+    /// The sum of every prime.
+    pub sum: u128,
+}
+
+/// Calculates the primes less than until and returns some statistics about them, computed while the primes are found instead of going through them again afterward.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// let stats = prime_stats(20u32);
+/// assert_eq!(stats.count, 8);
+/// assert_eq!(stats.largest, Some(19));
+/// assert_eq!(stats.max_gap, 4);
+/// assert_eq!(stats.twin_count, 4); // (3, 5), (5, 7), (11, 13), (17, 19)
+/// assert_eq!(stats.sum, 77);
+/// ```
+pub fn prime_stats<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+) -> PrimeStats<N> {
+    let two = N::one() + N::one();
+    let mut stats = PrimeStats {
+        count: 0,
+        largest: None,
+        max_gap: N::zero(),
+        twin_count: 0,
+        sum: 0,
+    };
+    prime_generator_map(until, Vec::new(), N::zero(), |p| {
+        if let Some(previous) = stats.largest {
+            let gap = p - previous;
+            stats.max_gap = stats.max_gap.max(gap);
+            if gap == two {
+                stats.twin_count += 1;
+            }
+        }
+        stats.count += 1;
+        stats.largest = Some(p);
+        stats.sum += p.to_u128().unwrap();
+    });
+    stats
+}
+
+/// The Euler-Mascheroni constant.
+const GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/// Approximates the logarithmic integral li(x), the integral of 1 / ln(t) from 0 to x, which is close to the number of primes up to x.
+///
+/// The series of Ramanujan is used, which converges quickly for every x greater than 1.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// assert!((li_approx(10.0) - 6.1656).abs() < 1e-4);
+/// assert!((li_approx(1e6) - 78627.55).abs() < 1e-2);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if x isn't greater than 1.
+pub fn li_approx(x: f64) -> f64 {
+    assert!(x > 1.0, "li is only approximated for x greater than 1");
+    let ln_x = x.ln();
+    let mut sum = 0.0;
+    // (-1)^(n-1) (ln x)^n / (n! 2^(n-1)), and the sum of 1/(2k+1) for k up to (n-1)/2.
+    let mut factor = 1.0;
+    let mut inner = 0.0;
+    for n in 1..1000 {
+        factor *= ln_x / n as f64 / if n == 1 { 1.0 } else { -2.0 };
+        if n % 2 == 1 {
+            inner += 1.0 / n as f64;
+        }
+        let term = factor * inner;
+        sum += term;
+        if term.abs() < sum.abs() * f64::EPSILON {
+            break;
+        }
+    }
+    GAMMA + ln_x.ln() + x.sqrt() * sum
+}
+
+/// Samples π(x) - li(x) at every multiple x of step less than until, where π(x) is the number of primes up to x and li(x) is approximated by [`li_approx`].
+///
+/// The difference is negative for every x that can be calculated, but it's known to change sign infinitely often, the first time somewhere below 10^317 (the Skewes number).
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// let samples = pi_minus_li(100u32, 25);
+/// assert_eq!(samples.iter().map(|s| s.0).collect::<Vec<_>>(), vec![25, 50, 75]);
+/// assert!(samples.iter().all(|s| s.1 < 0.0));
+/// ```
+///
+/// # Panics
+///
+/// The function panics if step is 0.
+pub fn pi_minus_li<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    step: N,
+) -> Vec<(N, f64)> {
+    assert!(!step.is_zero());
+    let primes = prime_generator(until, Vec::new(), N::zero());
+    num::range_step(step, until, step)
+        .map(|x| {
+            let pi = primes.partition_point(|&p| p <= x);
+            (x, pi as f64 - li_approx(x.to_f64().unwrap()))
+        })
+        .collect()
+}
+
+/// How many numbers every task of [`par_gap_histogram`] checks.
+const GAP_CHUNK_LEN: u32 = 1 << 16;
+
+/// The gaps between the primes of a chunk, and the primes at its ends to join it with the chunks next to it.
+struct ChunkGaps<N> {
+    first: N,
+    last: N,
+    gaps: BTreeMap<N, usize>,
+}
+
+/// Parallelely counts how many times every gap between consecutive primes less than until occurs.
+///
+/// The numbers are split in chunks, and every chunk counts the gaps between its own primes. The gap between the last prime of a chunk and the first prime of the next chunk that has any is added afterward.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+/// use std::collections::BTreeMap;
+///
+/// // 2, 3, 5, 7, 11, 13, 17, 19
+/// assert_eq!(par_gap_histogram(20u32), BTreeMap::from([(1, 1), (2, 4), (4, 2)]));
+/// ```
+pub fn par_gap_histogram<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync,
+>(
+    until: N,
+) -> BTreeMap<N, usize> {
+    let base_primes = prime_generator(until.sqrt() + N::one(), Vec::new(), N::zero());
+    let chunk_len = N::from_u32(GAP_CHUNK_LEN).unwrap_or(until);
+    let starts: Vec<N> = num::range_step(N::zero(), until, chunk_len).collect();
+
+    let chunks: Vec<Option<ChunkGaps<N>>> = starts
+        .into_par_iter()
+        .map(|start| {
+            let end = start
+                .checked_add(&chunk_len)
+                .map_or(until, |e| e.min(until));
+            let mut chunk: Option<ChunkGaps<N>> = None;
+            num::range(start, end)
+                .filter(|&n| is_prime(n, &base_primes))
+                .for_each(|p| match chunk.as_mut() {
+                    Some(c) => {
+                        *c.gaps.entry(p - c.last).or_insert(0) += 1;
+                        c.last = p;
+                    }
+                    None => {
+                        chunk = Some(ChunkGaps {
+                            first: p,
+                            last: p,
+                            gaps: BTreeMap::new(),
+                        })
+                    }
+                });
+            chunk
+        })
+        .collect();
+
+    let mut histogram = BTreeMap::new();
+    let mut previous_last: Option<N> = None;
+    for chunk in chunks.into_iter().flatten() {
+        if let Some(last) = previous_last {
+            *histogram.entry(chunk.first - last).or_insert(0) += 1;
+        }
+        for (gap, count) in chunk.gaps {
+            *histogram.entry(gap).or_insert(0) += count;
+        }
+        previous_last = Some(chunk.last);
+    }
+    histogram
+}
+
+/// Counts the primes less than until by their residue modulo modulus, to study how they are distributed between the residue classes.
+///
+/// By Dirichlet's theorem, the classes coprime to the modulus get the same share of the primes in the long run, but Chebyshev noticed that the classes of the quadratic non-residues stay ahead for a long time: for example, the primes congruent to 3 modulo 4 outnumber the ones congruent to 1 for almost every until.
+///
+/// The primes are found once with [`best_primes_below`]. The residues without any prime aren't in the map.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// let counts = residue_class_counts(100u32, 4);
+/// assert_eq!(counts[&1], 11);
+/// assert_eq!(counts[&2], 1);
+/// assert_eq!(counts[&3], 13);
+/// assert!(!counts.contains_key(&0));
+/// ```
+///
+/// # Panics
+///
+/// The function panics if modulus is 0.
+pub fn residue_class_counts<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates + Hash,
+>(
+    until: N,
+    modulus: N,
+) -> HashMap<N, usize> {
+    assert!(!modulus.is_zero(), "the modulus can't be 0");
+    let mut counts = HashMap::new();
+    for p in best_primes_below(until, Vec::new()) {
+        *counts.entry(p % modulus).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Returns a size × size grid, row by row from the top, marking which of its cells are prime when the numbers from 1 to size² are laid out in an Ulam spiral.
+///
+/// 1 is in the center, and the spiral goes right, up, left and down, turning counterclockwise. If size is even the center is on the bottom-left of the four central cells, so that the spiral fills the grid exactly.
+///
+/// The known primes must reach size, the square root of the largest number, and it's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// // 5 4 3
+/// // 6 1 2
+/// // 7 8 9
+/// let spiral = ulam_spiral(3, &[2u32, 3]);
+/// assert_eq!(spiral, vec![
+///     vec![true, false, true],
+///     vec![false, false, true],
+///     vec![true, false, false],
+/// ]);
+/// ```
+pub fn ulam_spiral<N: Roots + FromPrimitive + Copy>(
+    size: usize,
+    known_primes: &[N],
+) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![false; size]; size];
+    if size == 0 {
+        return grid;
+    }
+
+    // Right, up, left and down, with the rows growing downward.
+    const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (-1, 0), (0, -1), (1, 0)];
+    let (mut row, mut col) = (size as isize / 2, (size as isize - 1) / 2);
+    let mut n = 1;
+    let mut step_len = 1;
+    let mut direction = 0;
+    'spiral: loop {
+        // Every length is walked twice before growing.
+        for _ in 0..2 {
+            let (dr, dc) = DIRECTIONS[direction];
+            for _ in 0..step_len {
+                grid[row as usize][col as usize] =
+                    is_prime(N::from_usize(n).unwrap(), known_primes);
+                if n == size * size {
+                    break 'spiral;
+                }
+                n += 1;
+                row += dr;
+                col += dc;
+            }
+            direction = (direction + 1) % DIRECTIONS.len();
+        }
+        step_len += 1;
+    }
+    grid
+}
+
+/// The twin prime constant C₂, the product of p (p - 2) / (p - 1)² over every odd prime p.
+pub const TWIN_PRIME_CONSTANT: f64 = 0.660_161_815_846_869_6;
+
+/// Estimates how many pairs of twin primes are less than until with the conjecture of Hardy and Littlewood: 2 C₂ times the integral of 1 / (ln t)² from 2 to until.
+///
+/// Integrating by parts, the integral is li(until) - until / ln(until) - li(2) + 2 / ln(2), so it's calculated with [`li_approx`] instead of numerically. It's 0 if until is at most 2.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// // There are 1224 pairs below 100000.
+/// assert!((hardy_littlewood_twin_estimate(1e5) - 1224.0).abs() < 1224.0 * 0.03);
+/// ```
+pub fn hardy_littlewood_twin_estimate(until: f64) -> f64 {
+    if until <= 2.0 {
+        return 0.0;
+    }
+    let antiderivative = |x: f64| li_approx(x) - x / x.ln();
+    2.0 * TWIN_PRIME_CONSTANT * (antiderivative(until) - antiderivative(2.0))
+}
+
+/// Counts the pairs of twin primes less than until like [`prime_stats`], and returns the count along with [`hardy_littlewood_twin_estimate`] to compare them.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// let (count, estimate) = twin_primes_vs_estimate(1_000_000u32);
+/// assert_eq!(count, 8169);
+/// assert!((estimate / count as f64 - 1.0).abs() < 0.02);
+/// ```
+pub fn twin_primes_vs_estimate<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+) -> (usize, f64) {
+    (
+        prime_stats(until).twin_count,
+        hardy_littlewood_twin_estimate(until.to_f64().unwrap()),
+    )
+}
+
+/// Returns how many of n² + an + b are prime for n = 0, 1, 2... before the first one that isn't.
+///
+/// The values that are negative or don't fit in a u64 end the run.
+fn quadratic_run(a: i64, b: i64, known_primes: &[u64]) -> usize {
+    (0i128..)
+        .map(|n| n * n + a as i128 * n + b as i128)
+        .take_while(|&v| u64::try_from(v).is_ok_and(|v| ignorant_is_prime(v, known_primes)))
+        .count()
+}
+
+/// Searches the quadratic n² + an + b with a and b in the ranges specified that gives the longest run of primes for n = 0, 1, 2..., and returns a, b and the length of the run.
+///
+/// Every pair is tried, with the values of a split between threads, so the search costs |a_range| * |b_range| * L checks with [`ignorant_is_prime`], where L is the length of the runs (at most a few dozens, as every quadratic has a composite value before n = |b| + 1). Among the pairs with the same run, the one with the smallest a and then the smallest b is returned. If a range is empty, the result is (0, 0, 0).
+///
+/// Negative values and values that don't fit in a u64 end the run.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// // Euler's polynomial, prime for n from 0 to 39.
+/// assert_eq!(best_quadratic(0..=5, 0..=50, &[]), (1, 41, 40));
+/// ```
+pub fn best_quadratic(
+    a_range: RangeInclusive<i64>,
+    b_range: RangeInclusive<i64>,
+    known_primes: &[u64],
+) -> (i64, i64, usize) {
+    a_range
+        .into_par_iter()
+        .flat_map_iter(|a| b_range.clone().map(move |b| (a, b)))
+        .map(|(a, b)| (a, b, quadratic_run(a, b, known_primes)))
+        .max_by_key(|&(a, b, run)| (run, cmp::Reverse(a), cmp::Reverse(b)))
+        .unwrap_or((0, 0, 0))
+}
+
+/// Counts the numbers up to x that aren't divisible by any of the first a primes, with Legendre's recurrence φ(x, a) = φ(x, a - 1) - φ(x / pₐ, a - 1).
+fn legendre_phi(x: u64, a: usize, primes: &[u64], memo: &mut HashMap<(u64, usize), u64>) -> u64 {
+    if a == 0 || x == 0 {
+        return x;
+    }
+    if let Some(&phi) = memo.get(&(x, a)) {
+        return phi;
+    }
+    let phi =
+        legendre_phi(x, a - 1, primes, memo) - legendre_phi(x / primes[a - 1], a - 1, primes, memo);
+    memo.insert((x, a), phi);
+    phi
+}
+
+/// Counts the primes up to n, included, with Legendre's formula: π(n) = φ(n, a) + a - 1, where a is the number of primes up to the square root of n and φ(x, a) counts the numbers up to x not divisible by any of the first a primes.
+///
+/// Only the primes up to the square root of n are generated, and φ is calculated with a memoized recursion. It's the simplest of the combinatorial methods: it's meant for small and medium n, as the memo grows quickly with n.
+///
+/// # Examples
+///
+/// ```
+/// use prime::analysis::*;
+///
+/// assert_eq!(legendre_pi(100), 25);
+/// assert_eq!(legendre_pi(97), 25);
+/// assert_eq!(legendre_pi(1), 0);
+/// ```
+pub fn legendre_pi(n: u64) -> u64 {
+    if n < 2 {
+        return 0;
+    }
+    let primes = best_primes_below(n.sqrt() + 1, Vec::new());
+    let a = primes.len();
+    legendre_phi(n, a, &primes, &mut HashMap::new()) + a as u64 - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reciprocal_sum_test() {
+        assert_eq!(reciprocal_sum::<u32>(&[]), 0.0);
+        assert!((reciprocal_sum(&[2]) - 0.5).abs() < 1e-12);
+        assert!((reciprocal_sum(&[2, 3, 5, 7]) - 1.176_190_476).abs() < 1e-9);
+
+        // It grows like ln(ln(n)) plus the Meissel-Mertens constant.
+        let primes = prime_generator(100, Vec::new(), 0);
+        assert!((reciprocal_sum(&primes) - 1.802_817).abs() < 1e-6);
+        let primes = prime_generator(1_000_000, Vec::new(), 0);
+        let expected = 1_000_000f64.ln().ln() + 0.261_497_212_847_642_8;
+        assert!((reciprocal_sum(&primes) - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn mertens_test() {
+        let estimate = mertens_third_constant_estimate(1_000_000u32);
+        assert!((estimate - E_TO_MINUS_GAMMA).abs() / E_TO_MINUS_GAMMA < 0.01);
+    }
+
+    #[test]
+    fn prime_stats_test() {
+        assert_eq!(
+            prime_stats(0u32),
+            PrimeStats {
+                count: 0,
+                largest: None,
+                max_gap: 0,
+                twin_count: 0,
+                sum: 0
+            }
+        );
+
+        let stats = prime_stats(10_000u32);
+        let primes = prime_generator(10_000u32, Vec::new(), 0);
+        assert_eq!(stats.count, primes.len());
+        assert_eq!(stats.largest, primes.last().cloned());
+        assert_eq!(
+            stats.max_gap,
+            primes.windows(2).map(|w| w[1] - w[0]).max().unwrap()
+        );
+        assert_eq!(
+            stats.twin_count,
+            primes.windows(2).filter(|w| w[1] - w[0] == 2).count()
+        );
+        assert_eq!(stats.sum, primes.iter().map(|&p| p as u128).sum());
+
+        assert_eq!(
+            stats,
+            PrimeStats {
+                count: 1229,
+                largest: Some(9973),
+                max_gap: 36,
+                twin_count: 205,
+                sum: 5_736_396
+            }
+        );
+    }
+
+    #[test]
+    fn li_test() {
+        for (x, li) in [
+            (2.0, 1.045_163_780_117_493),
+            (10.0, 6.165_599_504_787_298),
+            (1000.0, 177.609_657_990_152_1),
+            (1e10, 455_055_614.586_134_6),
+        ] {
+            assert!((li_approx(x) - li).abs() < li * 1e-9, "{}", x);
+        }
+
+        let samples = pi_minus_li(100_000u64, 1000);
+        assert_eq!(samples.len(), 99);
+        assert!(samples.iter().all(|&(_, d)| d < 0.0));
+        // π(1000) = 168.
+        assert!((samples[0].1 - (168.0 - 177.609_657_990_152_1)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn gap_histogram_test() {
+        assert!(par_gap_histogram(3u32).is_empty());
+        assert_eq!(par_gap_histogram(4u32), BTreeMap::from([(1, 1)]));
+
+        let primes = prime_generator(100_000u64, Vec::new(), 0);
+        let mut expected = BTreeMap::new();
+        for w in primes.windows(2) {
+            *expected.entry(w[1] - w[0]).or_insert(0) += 1;
+        }
+        let histogram = par_gap_histogram(100_000u64);
+        assert_eq!(histogram, expected);
+        assert_eq!(histogram.values().sum::<usize>(), primes.len() - 1);
+        assert_eq!(histogram[&2], prime_stats(100_000u64).twin_count);
+        assert_eq!(histogram[&2], 1224);
+
+        // 65537 is the only prime of the second chunk, and its gap from 65521 is only counted when joining the chunks.
+        let histogram = par_gap_histogram(65_538u32);
+        let mut expected = BTreeMap::new();
+        for w in primes[..6543].windows(2) {
+            *expected.entry(w[1] as u32 - w[0] as u32).or_insert(0) += 1;
+        }
+        assert_eq!(histogram, expected);
+    }
+
+    #[test]
+    fn residue_class_counts_test() {
+        let counts = residue_class_counts(100u32, 4);
+        assert!(counts[&3] > counts[&1]);
+        assert_eq!(counts.values().sum::<usize>(), 25);
+
+        let primes = prime_generator(100_000u64, Vec::new(), 0);
+        for modulus in [1, 2, 3, 4, 10, 30, 97] {
+            let counts = residue_class_counts(100_000u64, modulus);
+            assert_eq!(counts.values().sum::<usize>(), primes.len());
+            for (&residue, &count) in &counts {
+                assert!(residue < modulus);
+                assert_eq!(
+                    count,
+                    primes.iter().filter(|&&p| p % modulus == residue).count()
+                );
+            }
+        }
+        // The primes congruent to 2 modulo 3 are still ahead at 100000.
+        let counts = residue_class_counts(100_000u32, 3);
+        assert!(counts[&2] > counts[&1]);
+        assert_eq!(counts[&0], 1);
+
+        assert!(residue_class_counts(2u32, 5).is_empty());
+    }
+
+    #[test]
+    fn ulam_spiral_test() {
+        // 17 16 15 14 13
+        // 18  5  4  3 12
+        // 19  6  1  2 11
+        // 20  7  8  9 10
+        // 21 22 23 24 25
+        let t = true;
+        let f = false;
+        assert_eq!(
+            ulam_spiral(5, &[2u32, 3, 5]),
+            vec![
+                vec![t, f, f, f, t],
+                vec![f, t, f, t, f],
+                vec![t, f, f, t, t],
+                vec![f, t, f, f, f],
+                vec![f, f, t, f, f],
+            ]
+        );
+        //  16 15 14 13
+        //   5  4  3 12
+        //   6  1  2 11
+        //   7  8  9 10
+        assert_eq!(
+            ulam_spiral(4, &[2u32, 3]),
+            vec![
+                vec![f, f, f, t],
+                vec![t, f, t, f],
+                vec![f, f, t, t],
+                vec![t, f, f, f],
+            ]
+        );
+        assert_eq!(ulam_spiral(0, &[2u32]), Vec::<Vec<bool>>::new());
+        assert_eq!(ulam_spiral(1, &[2u32]), vec![vec![false]]);
+        assert_eq!(
+            ulam_spiral(2, &[2u32]),
+            vec![vec![false, true], vec![false, true]]
+        );
+
+        // Every prime appears exactly once.
+        for size in [10, 11, 100] {
+            let primes = prime_generator(size + 1, Vec::new(), 0);
+            let count: usize = ulam_spiral(size, &primes)
+                .iter()
+                .map(|row| row.iter().filter(|&&p| p).count())
+                .sum();
+            assert_eq!(count, prime_generator(size * size + 1, Vec::new(), 0).len());
+        }
+    }
+
+    #[test]
+    fn twin_estimate_test() {
+        assert_eq!(hardy_littlewood_twin_estimate(0.0), 0.0);
+        assert_eq!(hardy_littlewood_twin_estimate(2.0), 0.0);
+        assert!(hardy_littlewood_twin_estimate(2.5) > 0.0);
+
+        let (count, estimate) = twin_primes_vs_estimate(100_000u64);
+        assert_eq!(count, 1224);
+        assert!((estimate / count as f64 - 1.0).abs() < 0.03, "{}", estimate);
+
+        // The relative error shrinks as until grows.
+        let mut previous = f64::INFINITY;
+        for until in [1000u64, 100_000, 3_000_000] {
+            let (count, estimate) = twin_primes_vs_estimate(until);
+            let error = (estimate / count as f64 - 1.0).abs();
+            assert!(error < previous, "{} {}", until, error);
+            previous = error;
+        }
+    }
+
+    #[test]
+    fn best_quadratic_test() {
+        let primes = prime_generator(1000u64, Vec::new(), 0);
+        assert_eq!(best_quadratic(0..=5, 0..=50, &primes), (1, 41, 40));
+        assert_eq!(best_quadratic(0..=5, 0..=50, &[]), (1, 41, 40));
+        // n² - n + 41 is prime for n = 0 too.
+        assert_eq!(best_quadratic(-2..=2, 0..=50, &[]), (-1, 41, 41));
+        // Project Euler 27.
+        assert_eq!(
+            best_quadratic(-999..=999, -1000..=1000, &primes),
+            (-61, 971, 71)
+        );
+
+        assert_eq!(quadratic_run(0, 2, &[]), 2);
+        assert_eq!(quadratic_run(0, -7, &[]), 0);
+        assert_eq!(quadratic_run(i64::MAX, i64::MAX, &[]), 0);
+        assert_eq!(
+            best_quadratic(RangeInclusive::new(1, 0), 0..=50, &[]),
+            (0, 0, 0)
+        );
+        assert_eq!(best_quadratic(0..=5, -10..=1, &[]), (0, -10, 0));
+    }
+
+    #[test]
+    fn legendre_pi_test() {
+        let primes = prime_generator(1_000_001u64, Vec::new(), 0);
+        for n in (0..1000).chain([7919, 65_536, 999_983, 1_000_000]) {
+            let expected = primes.partition_point(|&p| p <= n) as u64;
+            assert_eq!(legendre_pi(n), expected, "{}", n);
+        }
+    }
+}
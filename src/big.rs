@@ -0,0 +1,362 @@
+use num::{BigInt, BigUint, Integer, One, Signed, ToPrimitive, Zero};
+#[cfg(feature = "rand")]
+use rand::Rng;
+
+/// The primes used to reject most candidates with a cheap division before running the probabilistic tests.
+const SMALL_PRIMES: [u32; 25] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+];
+
+/// The bases of the Miller-Rabin test used by [`is_probable_prime_big`]: the result is exact for every n less than 3.3 * 10^24.
+const MILLER_RABIN_BASES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// Handles the numbers that are divisible by one of the small primes, returning `None` for the others.
+fn small_divisibility(n: &BigUint) -> Option<bool> {
+    if n < &BigUint::from(2u32) {
+        return Some(false);
+    }
+    SMALL_PRIMES
+        .iter()
+        .find(|&&p| (n % p).is_zero())
+        .map(|&p| n == &BigUint::from(p))
+}
+
+/// Runs the strong probable prime test to the base a on an odd n greater than a.
+fn strong_probable_prime(n: &BigUint, a: &BigUint) -> bool {
+    let n_minus_one = n - 1u32;
+    let s = n_minus_one.trailing_zeros().unwrap();
+    let d = &n_minus_one >> s;
+
+    let mut x = a.modpow(&d, n);
+    if x.is_one() || x == n_minus_one {
+        return true;
+    }
+    for _ in 1..s {
+        x = &x * &x % n;
+        if x == n_minus_one {
+            return true;
+        }
+    }
+    false
+}
+
+/// Calculates the Jacobi symbol (a/n) for an odd n.
+fn jacobi(a: &BigUint, n: &BigUint) -> i32 {
+    let mut a = a % n;
+    let mut n = n.clone();
+    let mut result = 1;
+    while !a.is_zero() {
+        let twos = a.trailing_zeros().unwrap();
+        a >>= twos;
+        let n_mod_8 = (&n % 8u32).to_u32().unwrap();
+        if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+            result = -result;
+        }
+        if (&a % 4u32).to_u32() == Some(3) && n_mod_8 % 4 == 3 {
+            result = -result;
+        }
+        std::mem::swap(&mut a, &mut n);
+        a %= &n;
+    }
+    if n.is_one() {
+        result
+    } else {
+        0
+    }
+}
+
+/// Halves x modulo the odd n.
+fn half_mod(x: BigUint, n: &BigUint) -> BigUint {
+    if x.is_odd() {
+        (x + n) >> 1
+    } else {
+        x >> 1
+    }
+}
+
+/// Runs the strong Lucas probable prime test with the parameters chosen by Selfridge's method on an odd n that isn't a perfect square.
+fn strong_lucas_probable_prime(n: &BigUint) -> bool {
+    let n_int = BigInt::from(n.clone());
+    let to_mod_n = |x: &BigInt| x.mod_floor(&n_int).to_biguint().unwrap();
+
+    // D is the first of 5, -7, 9, -11, ... with (D/n) = -1.
+    let mut d = BigInt::from(5);
+    loop {
+        match jacobi(&to_mod_n(&d), n) {
+            -1 => break,
+            0 if d.abs() != n_int => return false,
+            _ => {}
+        }
+        d = if d.is_positive() { -d - 2 } else { -d + 2 };
+    }
+    let q = to_mod_n(&((BigInt::one() - &d) / 4));
+    let d = to_mod_n(&d);
+
+    // n + 1 = k * 2^s with k odd. P is 1.
+    let n_plus_one = n + 1u32;
+    let s = n_plus_one.trailing_zeros().unwrap();
+    let k = &n_plus_one >> s;
+
+    let mut u = BigUint::one();
+    let mut v = BigUint::one();
+    let mut q_k = q.clone();
+    for bit in (0..k.bits() - 1).rev() {
+        u = &u * &v % n;
+        v = (&v * &v + n * 2u32 - &q_k * 2u32 % n) % n;
+        q_k = &q_k * &q_k % n;
+        if k.bit(bit) {
+            let next_u = half_mod(&u + &v, n);
+            v = half_mod(&d * &u + &v, n) % n;
+            u = next_u % n;
+            q_k = &q_k * &q % n;
+        }
+    }
+
+    if u.is_zero() || v.is_zero() {
+        return true;
+    }
+    for _ in 1..s {
+        v = (&v * &v + n * 2u32 - &q_k * 2u32 % n) % n;
+        if v.is_zero() {
+            return true;
+        }
+        q_k = &q_k * &q_k % n;
+    }
+    false
+}
+
+/// Checks if n is a probable prime with the Baillie-PSW test: a strong probable prime test to the base 2 followed by a strong Lucas probable prime test.
+///
+/// No composite number is known to pass it, and none exist below 2^64.
+///
+/// # Examples
+///
+/// ```
+/// use num::BigUint;
+/// use prime::big::*;
+///
+/// assert!(baillie_psw(&BigUint::from(1_000_000_007u32)));
+/// assert!(!baillie_psw(&BigUint::from(3_215_031_751u32))); // A strong pseudoprime to the bases 2, 3, 5 and 7.
+/// ```
+pub fn baillie_psw(n: &BigUint) -> bool {
+    if let Some(result) = small_divisibility(n) {
+        return result;
+    }
+    let sqr = n.sqrt();
+    strong_probable_prime(n, &BigUint::from(2u32))
+        && &sqr * &sqr != *n
+        && strong_lucas_probable_prime(n)
+}
+
+/// Checks if n is a probable prime with the Miller-Rabin test to the first 12 primes.
+///
+/// The result is exact for every n less than 3.3 * 10^24. Above that a composite number could pass, even if it's extremely unlikely for one that wasn't built on purpose: use [`baillie_psw`] to be more confident.
+///
+/// # Examples
+///
+/// ```
+/// use num::BigUint;
+/// use prime::big::*;
+///
+/// assert!(is_probable_prime_big(&BigUint::from(97u32)));
+/// assert!(!is_probable_prime_big(&BigUint::from(3_215_031_751u32)));
+/// ```
+pub fn is_probable_prime_big(n: &BigUint) -> bool {
+    if let Some(result) = small_divisibility(n) {
+        return result;
+    }
+    MILLER_RABIN_BASES
+        .iter()
+        .all(|&a| strong_probable_prime(n, &BigUint::from(a)))
+}
+
+/// Returns the first probable prime greater than n, according to [`is_probable_prime_big`].
+///
+/// Only odd candidates are tested. By Bertrand's postulate the search always ends before 2n.
+///
+/// # Examples
+///
+/// ```
+/// use num::BigUint;
+/// use prime::big::*;
+///
+/// assert_eq!(next_prime_big(&BigUint::from(100u32)), BigUint::from(101u32));
+/// assert_eq!(next_prime_big(&BigUint::from(1u32)), BigUint::from(2u32));
+/// ```
+pub fn next_prime_big(n: &BigUint) -> BigUint {
+    if n < &BigUint::from(2u32) {
+        return BigUint::from(2u32);
+    }
+    let mut candidate = if n.is_even() { n + 1u32 } else { n + 2u32 };
+    while !is_probable_prime_big(&candidate) {
+        candidate += 2u32;
+    }
+    candidate
+}
+
+/// Returns the last probable prime less than n, according to [`is_probable_prime_big`], or `None` if n is at most 2.
+///
+/// Only odd candidates are tested, apart from 2.
+///
+/// # Examples
+///
+/// ```
+/// use num::BigUint;
+/// use prime::big::*;
+///
+/// assert_eq!(prev_prime_big(&BigUint::from(100u32)), Some(BigUint::from(97u32)));
+/// assert_eq!(prev_prime_big(&BigUint::from(2u32)), None);
+/// ```
+pub fn prev_prime_big(n: &BigUint) -> Option<BigUint> {
+    if n <= &BigUint::from(2u32) {
+        return None;
+    }
+    if n == &BigUint::from(3u32) {
+        return Some(BigUint::from(2u32));
+    }
+    let mut candidate = if n.is_even() { n - 1u32 } else { n - 2u32 };
+    while !is_probable_prime_big(&candidate) {
+        candidate -= 2u32;
+    }
+    Some(candidate)
+}
+
+/// Returns a random number with exactly the given amount of bits, that is with the highest one set.
+#[cfg(feature = "rand")]
+fn random_bits(bits: u32, rng: &mut impl Rng) -> BigUint {
+    let mut bytes = vec![0; bits.div_ceil(8) as usize];
+    rng.fill_bytes(&mut bytes);
+    let mut n = BigUint::from_bytes_le(&bytes);
+    n &= (BigUint::one() << bits) - 1u32;
+    n.set_bit(u64::from(bits - 1), true);
+    n
+}
+
+/// Returns a random safe prime with exactly the given amount of bits: a prime p such that (p - 1) / 2 is also prime, as needed by the Diffie-Hellman key exchange.
+///
+/// Random odd numbers q with one bit less are drawn until both q and 2q + 1 pass [`is_probable_prime_big`].
+/// Since 2q + 1 is cheaper to reject with a small division, it's tested first.
+///
+/// Requires the `rand` feature.
+///
+/// # Examples
+///
+/// ```
+/// use prime::big::*;
+/// use rand::SeedableRng;
+///
+/// let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(0);
+/// let p = safe_prime(64, &mut rng);
+/// assert_eq!(p.bits(), 64);
+/// assert!(is_probable_prime_big(&p));
+/// assert!(is_probable_prime_big(&(p >> 1)));
+/// ```
+///
+/// # Panics
+///
+/// The function panics if bits is less than 3, since the smallest safe prime is 5.
+#[cfg(feature = "rand")]
+pub fn safe_prime(bits: u32, rng: &mut impl Rng) -> BigUint {
+    assert!(bits >= 3, "there are no safe primes with less than 3 bits");
+    loop {
+        let mut q = random_bits(bits - 1, rng);
+        q.set_bit(0, true);
+        let p: BigUint = &q * 2u32 + 1u32;
+        if small_divisibility(&p).is_none_or(|prime| prime)
+            && is_probable_prime_big(&p)
+            && is_probable_prime_big(&q)
+        {
+            return p;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculator::*;
+
+    #[test]
+    fn small_numbers_test() {
+        for n in 0u64..10_000 {
+            let big = BigUint::from(n);
+            assert_eq!(baillie_psw(&big), deterministic_is_prime(n), "{}", n);
+            assert_eq!(
+                is_probable_prime_big(&big),
+                deterministic_is_prime(n),
+                "{}",
+                n
+            );
+        }
+    }
+
+    #[test]
+    fn pseudoprimes_test() {
+        // Strong pseudoprimes to the base 2.
+        for n in [
+            2047u64,
+            3277,
+            4033,
+            4681,
+            8321,
+            3_215_031_751,
+            3_825_123_056_546_413_051,
+        ] {
+            assert!(!baillie_psw(&BigUint::from(n)), "{}", n);
+            assert!(!is_probable_prime_big(&BigUint::from(n)), "{}", n);
+        }
+        // Strong Lucas pseudoprimes, which aren't strong pseudoprimes to the base 2.
+        for n in [5459u32, 5777, 10877, 16109, 18971] {
+            assert!(!strong_probable_prime(
+                &BigUint::from(n),
+                &BigUint::from(2u32)
+            ));
+            assert!(strong_lucas_probable_prime(&BigUint::from(n)), "{}", n);
+        }
+        // Mersenne primes.
+        for p in [61, 89, 107, 127, 521] {
+            let n = (BigUint::one() << p) - 1u32;
+            assert!(baillie_psw(&n), "2^{} - 1", p);
+            assert!(is_probable_prime_big(&n), "2^{} - 1", p);
+        }
+    }
+
+    #[test]
+    fn next_prime_big_test() {
+        // 10^99 has 100 digits and is obviously composite.
+        let n = BigUint::from(10u32).pow(99);
+        let next = next_prime_big(&n);
+        assert!(baillie_psw(&next));
+        assert!(next.is_odd());
+        let mut candidate = &n + 1u32;
+        while candidate < next {
+            assert!(!baillie_psw(&candidate));
+            candidate += 1u32;
+        }
+        assert_eq!(prev_prime_big(&(&next + 1u32)), Some(next.clone()));
+
+        for n in 0u64..1000 {
+            let next = (n + 1..).find(|&m| deterministic_is_prime(m)).unwrap();
+            assert_eq!(next_prime_big(&BigUint::from(n)), BigUint::from(next));
+            let prev = (2..n).rev().find(|&m| deterministic_is_prime(m));
+            assert_eq!(prev_prime_big(&BigUint::from(n)), prev.map(BigUint::from));
+        }
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn safe_prime_test() {
+        use rand::SeedableRng;
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(42);
+        for bits in [3, 4, 5, 8, 16, 64, 128, 256] {
+            let p = safe_prime(bits, &mut rng);
+            assert_eq!(p.bits(), u64::from(bits));
+            assert!(baillie_psw(&p), "{}", p);
+            assert!(baillie_psw(&((&p - 1u32) / 2u32)), "{}", p);
+        }
+        for _ in 0..100 {
+            let p = safe_prime(5, &mut rng);
+            assert!(p == BigUint::from(23u32), "{}", p);
+        }
+    }
+}
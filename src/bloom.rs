@@ -0,0 +1,135 @@
+use crate::calculator::*;
+use num::integer::Roots;
+use num::{FromPrimitive, ToPrimitive};
+
+/// A Bloom filter of a list of primes: it answers whether a number is in the list with no false negatives and a small rate of false positives, using a few bits per prime instead of the whole number.
+///
+/// Every number sets or looks at `hashes` bits, chosen by double hashing.
+///
+/// # Examples
+///
+/// ```
+/// use prime::bloom::*;
+///
+/// let bloom = PrimeBloom::from_primes(&[2u32, 3, 5, 7, 11, 13, 17, 19], 0.01);
+/// assert!(bloom.probably_contains(13));
+/// assert!(bloom.is_prime(13));
+/// assert!(!bloom.is_prime(15));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimeBloom {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+/// Mixes the bits of x with the finalizer of SplitMix64.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+impl PrimeBloom {
+    /// Builds the filter of the primes, sized so that the rate of false positives is about fp_rate.
+    ///
+    /// With n primes, the filter takes -n ln(fp_rate) / ln(2)² bits and -log2(fp_rate) hashes: about 10 bits per prime for 1%.
+    ///
+    /// # Panics
+    ///
+    /// The function panics if fp_rate isn't between 0 and 1, excluded.
+    pub fn from_primes<N: ToPrimitive>(primes: &[N], fp_rate: f64) -> Self {
+        assert!(
+            fp_rate > 0.0 && fp_rate < 1.0,
+            "the rate of false positives must be between 0 and 1"
+        );
+        let ln2 = std::f64::consts::LN_2;
+        let len = primes.len().max(1) as f64;
+        let bit_len = (-len * fp_rate.ln() / (ln2 * ln2)).ceil().max(64.0);
+        let hashes = (-fp_rate.log2()).round().max(1.0) as u32;
+
+        let mut bloom = PrimeBloom {
+            bits: vec![0; (bit_len as usize).div_ceil(64)],
+            hashes,
+        };
+        for p in primes {
+            bloom.insert(p.to_u64().unwrap());
+        }
+        bloom
+    }
+
+    /// Returns the positions of the bits of n.
+    fn positions(&self, n: u64) -> impl Iterator<Item = usize> {
+        let bit_len = self.bits.len() as u64 * 64;
+        let h1 = mix(n);
+        let h2 = mix(h1) | 1;
+        (0..self.hashes as u64)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_len) as usize)
+    }
+
+    /// Adds a number to the filter, like the primes found after it was built.
+    pub fn insert(&mut self, n: u64) {
+        for i in self.positions(n).collect::<Vec<_>>() {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    /// Returns `false` if n is certainly not in the list, `true` if it probably is.
+    pub fn probably_contains<N: ToPrimitive>(&self, n: N) -> bool {
+        n.to_u64().is_some_and(|n| {
+            self.positions(n)
+                .all(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+        })
+    }
+
+    /// Checks if n is prime, assuming the list contains every prime up to n: the numbers the filter rejects are composite, and the others are confirmed with [`ignorant_is_prime`].
+    ///
+    /// The exact check only runs for the primes and the few false positives.
+    pub fn is_prime<N: Roots + FromPrimitive + ToPrimitive + Copy>(&self, n: N) -> bool {
+        self.probably_contains(n) && ignorant_is_prime(n, &[])
+    }
+
+    /// The memory taken by the bits of the filter, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::*;
+
+    #[test]
+    fn bloom_test() {
+        let primes = prime_generator(1_000_000u64, Vec::new(), 0);
+        for fp_rate in [0.1, 0.01, 0.001] {
+            let bloom = PrimeBloom::from_primes(&primes, fp_rate);
+            assert!(primes.iter().all(|&p| bloom.probably_contains(p)));
+            assert!(bloom.size_in_bytes() < primes.len() * 8);
+
+            let composites = (0..1_000_000u64).filter(|n| primes.binary_search(n).is_err());
+            let (total, positives) = composites.fold((0, 0), |(total, positives), n| {
+                (total + 1, positives + bloom.probably_contains(n) as usize)
+            });
+            let rate = positives as f64 / total as f64;
+            assert!(rate < fp_rate * 1.5, "{} {}", fp_rate, rate);
+        }
+
+        let bloom = PrimeBloom::from_primes(&primes, 0.01);
+        for n in 0..10_000u32 {
+            assert_eq!(bloom.is_prime(n), primes.binary_search(&(n as u64)).is_ok());
+        }
+        assert!(!bloom.probably_contains(-1));
+
+        let mut bloom = PrimeBloom::from_primes::<u64>(&[], 0.01);
+        assert!(!bloom.probably_contains(1_000_003));
+        bloom.insert(1_000_003);
+        assert!(bloom.probably_contains(1_000_003));
+    }
+
+    #[test]
+    #[should_panic]
+    fn bloom_panic() {
+        PrimeBloom::from_primes(&[2u32], 0.0);
+    }
+}
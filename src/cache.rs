@@ -0,0 +1,464 @@
+use crate::calculator::*;
+use crate::generator::*;
+use num::integer::Roots;
+use num::{CheckedAdd, FromPrimitive, ToPrimitive};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+static GLOBAL: OnceLock<Mutex<Vec<u64>>> = OnceLock::new();
+
+/// Returns the list of primes shared by the whole process, which starts empty and is extended by [`is_prime_cached`] and [`nth_prime_cached`].
+///
+/// The list is always ordered and contains every prime up to its last element, so it can be passed to any function of this crate. It's never shrunk: its memory is only released when the process ends.
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+///
+/// nth_prime_cached(10);
+/// assert!(global().lock().unwrap().len() >= 10);
+/// ```
+pub fn global() -> &'static Mutex<Vec<u64>> {
+    GLOBAL.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Locks the global list. A thread that panicked while holding the lock can't have left the list invalid, as it's only ever extended with [`extend_primes`].
+fn lock() -> MutexGuard<'static, Vec<u64>> {
+    global().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Calculates if the number n is prime with [`is_prime`], extending the [`global`] list up to the square root of n first if it doesn't reach it.
+///
+/// The lock is held during the whole check, so the threads calling this function are serialized, but the primes are only calculated once. Checking a number near u64::MAX makes the list grow to about 200 million primes (1.6 GB).
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+///
+/// assert!(is_prime_cached(1_000_003));
+/// assert!(!is_prime_cached(1_000_001));
+/// ```
+pub fn is_prime_cached(n: u64) -> bool {
+    let mut primes = lock();
+    let sqr = n.sqrt();
+    if primes.last().is_none_or(|&p| p < sqr) {
+        extend_primes(&mut primes, sqr + 1);
+    }
+    is_prime(n, &primes)
+}
+
+/// Returns the nth prime, starting from 1, extending the [`global`] list until it contains it first.
+///
+/// The lock is held while the list is extended, so the primes are only calculated once.
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+///
+/// assert_eq!(nth_prime_cached(1), 2);
+/// assert_eq!(nth_prime_cached(6), 13);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if n is 0.
+pub fn nth_prime_cached(n: usize) -> u64 {
+    assert!(n != 0, "the primes are counted from 1");
+    let mut primes = lock();
+    if primes.len() < n {
+        extend_primes(&mut primes, nth_prime_bound(n));
+    }
+    primes[n - 1]
+}
+
+/// A list of primes along with how far it was calculated, which can be saved and given back to [`par_prime_generator_resume`] to continue the calculation later.
+///
+/// With the `serde` feature, it can be serialized and deserialized.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrimeCache<N> {
+    /// Every prime less than computed_up_to, in order. There can be more after it.
+    pub primes: Vec<N>,
+    /// Every prime less than this number is in the Vec.
+    pub computed_up_to: N,
+}
+
+/// The computed_up_to of a [`GenResult`] is already where its list stops being complete, even if start_from skipped some numbers.
+impl<N> From<GenResult<N>> for PrimeCache<N> {
+    fn from(result: GenResult<N>) -> Self {
+        PrimeCache {
+            primes: result.primes,
+            computed_up_to: result.computed_up_to,
+        }
+    }
+}
+
+/// Continues the calculation of a [`PrimeCache`] from computed_up_to to until with [`par_prime_generator`], and returns the updated cache.
+///
+/// If the cache already reaches until, it's returned as it is. Otherwise, the primes greater than or equal to computed_up_to are discarded and calculated again, as the list may not be complete after it.
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+///
+/// let cache = par_prime_generator_resume(PrimeCache::default(), 10u32);
+/// assert_eq!(cache.primes, vec![2, 3, 5, 7]);
+/// assert_eq!(cache.computed_up_to, 10);
+///
+/// let cache = par_prime_generator_resume(cache, 20);
+/// assert_eq!(cache.primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(cache.computed_up_to, 20);
+/// ```
+pub fn par_prime_generator_resume<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    cache: PrimeCache<N>,
+    until: N,
+) -> PrimeCache<N> {
+    if cache.computed_up_to >= until {
+        return cache;
+    }
+    // The generators start after the last prime, so the ones after computed_up_to, which may have gaps before them, are calculated again.
+    let mut primes = cache.primes;
+    primes.truncate(primes.partition_point(|&p| p < cache.computed_up_to));
+    // The generators step over the even numbers, so they have to start from an odd one.
+    let start_from = if cache.computed_up_to.is_even() {
+        cache.computed_up_to + N::one()
+    } else {
+        cache.computed_up_to
+    };
+    PrimeCache {
+        primes: par_prime_generator(until, primes, start_from),
+        computed_up_to: until,
+    }
+}
+
+/// Like [`par_prime_generator_resume`], but the calculation is done one chunk at a time, and before every chunk it stops if `cancel` is true.
+///
+/// The computed_up_to of the returned cache is the end of the last chunk that was finished: the cache can be saved and passed again to continue.
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let cancel = AtomicBool::new(false);
+/// let cache = par_prime_generator_resume_cancellable(PrimeCache::default(), 10u32, 4, &cancel);
+/// assert_eq!(cache, PrimeCache { primes: vec![2, 3, 5, 7], computed_up_to: 10 });
+///
+/// // Cancelled before the first chunk, so nothing changes.
+/// let cancel = AtomicBool::new(true);
+/// assert_eq!(par_prime_generator_resume_cancellable(cache.clone(), 100, 4, &cancel), cache);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if chunk_size is 0.
+pub fn par_prime_generator_resume_cancellable<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    cache: PrimeCache<N>,
+    until: N,
+    chunk_size: N,
+    cancel: &AtomicBool,
+) -> PrimeCache<N> {
+    assert!(!chunk_size.is_zero());
+    if cache.computed_up_to >= until {
+        return cache;
+    }
+
+    let reached = Cell::new(cache.computed_up_to);
+    let mut primes = par_prime_generator_map_chunks_from(
+        until,
+        cache.primes,
+        ChunkOptions {
+            start_from: cache.computed_up_to,
+            chunk_size,
+        },
+        |_, end| {
+            if cancel.load(Ordering::Relaxed) {
+                return false;
+            }
+            reached.set(end);
+            true
+        },
+        |_| {},
+    );
+    // 2 and 3 are added even if the calculation is cancelled before the first chunk.
+    let computed_up_to = reached.get();
+    primes.truncate(primes.partition_point(|&p| p < computed_up_to));
+    PrimeCache {
+        primes,
+        computed_up_to,
+    }
+}
+
+/// A calculation stopped by [`par_prime_generator_resumable`]: the primes found so far and the number it arrived to, which can be given back to resume it.
+///
+/// It's the same as a [`PrimeCache`], and can be converted to and from one.
+///
+/// With the `serde` feature, it can be serialized and deserialized.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint<N> {
+    /// Every prime less than arrived_to, in order.
+    pub primes: Vec<N>,
+    /// The end of the last chunk that was fully processed.
+    pub arrived_to: N,
+}
+
+impl<N> From<PrimeCache<N>> for Checkpoint<N> {
+    fn from(cache: PrimeCache<N>) -> Self {
+        Checkpoint {
+            primes: cache.primes,
+            arrived_to: cache.computed_up_to,
+        }
+    }
+}
+
+impl<N> From<Checkpoint<N>> for PrimeCache<N> {
+    fn from(checkpoint: Checkpoint<N>) -> Self {
+        PrimeCache {
+            primes: checkpoint.primes,
+            computed_up_to: checkpoint.arrived_to,
+        }
+    }
+}
+
+/// Parallelely calculates the primes from the checkpoint to until, one chunk at a time, and returns the checkpoint it arrived to, with [`par_prime_generator_resume_cancellable`].
+///
+/// Before every chunk, the calculation stops if `cancel` is true. If the returned checkpoint didn't arrive to until, it can be passed again to continue the calculation.
+///
+/// # Examples
+///
+/// ```
+/// use prime::cache::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let cancel = AtomicBool::new(false);
+/// let checkpoint = par_prime_generator_resumable(10u32, Checkpoint::default(), 4, &cancel);
+/// assert_eq!(checkpoint, Checkpoint { primes: vec![2, 3, 5, 7], arrived_to: 10 });
+///
+/// let checkpoint = par_prime_generator_resumable(20, checkpoint, 4, &cancel);
+/// assert_eq!(checkpoint.primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(checkpoint.arrived_to, 20);
+///
+/// // Cancelled before the first chunk, so nothing changes.
+/// let cancel = AtomicBool::new(true);
+/// assert_eq!(par_prime_generator_resumable(100, checkpoint.clone(), 4, &cancel), checkpoint);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if chunk_size is 0.
+pub fn par_prime_generator_resumable<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    checkpoint: Checkpoint<N>,
+    chunk_size: N,
+    cancel: &AtomicBool,
+) -> Checkpoint<N> {
+    par_prime_generator_resume_cancellable(checkpoint.into(), until, chunk_size, cancel).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp;
+    use std::thread;
+
+    #[test]
+    fn cached_test() {
+        let expected = prime_generator(100_000u64, Vec::new(), 0);
+        assert_eq!(nth_prime_cached(1), 2);
+        assert_eq!(nth_prime_cached(6), 13);
+        assert_eq!(nth_prime_cached(expected.len()), *expected.last().unwrap());
+
+        for n in [0, 1, 2, 3, 4, 9, 25, 10_000_019, 99_999_999_977] {
+            assert_eq!(is_prime_cached(n), deterministic_is_prime(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn concurrent_test() {
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                thread::spawn(move || {
+                    (t * 1000..1_000_000)
+                        .step_by(7919)
+                        .all(|n| is_prime_cached(n * n + 1) == deterministic_is_prime(n * n + 1))
+                })
+            })
+            .collect();
+        assert!(handles.into_iter().all(|h| h.join().unwrap()));
+
+        // Every prime was added once, even if the threads raced to extend the list.
+        let primes = global().lock().unwrap();
+        assert!(primes.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            *primes,
+            prime_generator(*primes.last().unwrap() + 1, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn resume_test() {
+        let until = 1_000_000u64;
+        let expected = prime_generator(until, Vec::new(), 0);
+
+        let mut cache = PrimeCache::default();
+        for step in [0, 10, 1000, 123_457, 500_000, until, 10] {
+            cache = par_prime_generator_resume(cache, step);
+            assert_eq!(cache.computed_up_to, cmp::max(step, cache.computed_up_to));
+            assert!(cache.primes.iter().all(|&p| p < cache.computed_up_to));
+            assert_eq!(
+                cache.primes,
+                prime_generator(cache.computed_up_to, Vec::new(), 0)
+            );
+        }
+        assert_eq!(cache.primes, expected);
+
+        // The cache can go further than until.
+        let cache = PrimeCache::from(prime_generator_detailed(
+            20,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23],
+            0,
+        ));
+        assert_eq!(cache.computed_up_to, 24);
+        assert_eq!(par_prime_generator_resume(cache.clone(), 20), cache);
+        assert_eq!(par_prime_generator_resume(cache.clone(), 24), cache);
+        assert_eq!(
+            par_prime_generator_resume(cache, 30).primes,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+
+        // start_from skipped the numbers from 5 to 49, so the primes found after them aren't trusted.
+        let cache = PrimeCache::from(prime_generator_detailed(100u64, Vec::new(), 50));
+        assert_eq!(cache.computed_up_to, 5);
+        assert_eq!(
+            par_prime_generator_resume(cache, 200).primes,
+            prime_generator(200, Vec::new(), 0)
+        );
+        let cache = PrimeCache {
+            primes: vec![2, 3, 5, 7, 53, 59],
+            computed_up_to: 9,
+        };
+        assert_eq!(
+            par_prime_generator_resume(cache, 200).primes,
+            prime_generator(200, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn resume_cancellable_test() {
+        let until = 1_000_000u64;
+        let expected = prime_generator(until, Vec::new(), 0);
+        let go = AtomicBool::new(false);
+        let stop = AtomicBool::new(true);
+
+        // Resume from caches that stopped at odd, even and chunk-unaligned numbers.
+        let mut cache = PrimeCache::default();
+        for step in [0, 1, 2, 3, 10, 1000, 123_457, 500_000, until, 10] {
+            cache = par_prime_generator_resume_cancellable(cache, step, 7_001, &go);
+            assert_eq!(cache.computed_up_to, cmp::max(step, cache.computed_up_to));
+            assert_eq!(
+                cache.primes[..],
+                expected[..expected.partition_point(|&p| p < cache.computed_up_to)]
+            );
+        }
+        assert_eq!(cache.primes, expected);
+
+        // The cache can go further than until.
+        let cache = PrimeCache::from(prime_generator_detailed(
+            20,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23],
+            0,
+        ));
+        assert_eq!(cache.computed_up_to, 24);
+        assert_eq!(
+            par_prime_generator_resume_cancellable(cache.clone(), 20, 10, &go),
+            cache
+        );
+        assert_eq!(
+            par_prime_generator_resume_cancellable(cache.clone(), 24, 10, &go),
+            cache
+        );
+        assert_eq!(
+            par_prime_generator_resume_cancellable(cache, 30, 10, &go).primes,
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+
+        // start_from skipped the numbers from 5 to 49, so the primes found after them aren't trusted.
+        let cache = PrimeCache::from(prime_generator_detailed(100u64, Vec::new(), 50));
+        assert_eq!(cache.computed_up_to, 5);
+        assert_eq!(
+            par_prime_generator_resume_cancellable(cache, 200, 10, &go).primes,
+            prime_generator(200, Vec::new(), 0)
+        );
+
+        // The primes found after computed_up_to are discarded, and a cancelled calculation changes nothing else.
+        let partial = PrimeCache {
+            primes: vec![2, 3, 5, 7, 11, 13, 29],
+            computed_up_to: 15,
+        };
+        let cancelled = par_prime_generator_resume_cancellable(partial.clone(), 100, 10, &stop);
+        assert_eq!(cancelled.primes, vec![2, 3, 5, 7, 11, 13]);
+        assert_eq!(cancelled.computed_up_to, 15);
+        let cancelled =
+            par_prime_generator_resume_cancellable(PrimeCache::default(), 100u64, 10, &stop);
+        assert_eq!(cancelled, PrimeCache::default());
+        assert_eq!(
+            par_prime_generator_resume_cancellable(partial, 100, 10, &go).primes,
+            expected[..25]
+        );
+    }
+
+    #[test]
+    fn resumable_test() {
+        let expected = prime_generator(100_000u64, Vec::new(), 0);
+        let go = AtomicBool::new(false);
+        let stop = AtomicBool::new(true);
+
+        let mut checkpoint = Checkpoint::default();
+        for until in [1, 2, 3, 10, 999, 30_000, 30_001, 100_000] {
+            checkpoint = par_prime_generator_resumable(until, checkpoint, 7_001, &go);
+            assert_eq!(checkpoint.arrived_to, until);
+            assert_eq!(
+                checkpoint.primes[..],
+                expected[..expected.partition_point(|&p| p < until)]
+            );
+        }
+        assert_eq!(PrimeCache::from(checkpoint.clone()).computed_up_to, 100_000);
+        assert_eq!(
+            Checkpoint::from(PrimeCache::from(checkpoint.clone())),
+            checkpoint
+        );
+
+        let cancelled = par_prime_generator_resumable(100u64, Checkpoint::default(), 10, &stop);
+        assert_eq!(cancelled, Checkpoint::default());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_resume_test() {
+        let until = 1_000_000u64;
+        let uninterrupted = par_prime_generator_resume(PrimeCache::default(), until);
+
+        let saved = bincode::serialize(&par_prime_generator_resume(
+            PrimeCache::default(),
+            300_007u64,
+        ))
+        .unwrap();
+        let cache: PrimeCache<u64> = bincode::deserialize(&saved).unwrap();
+        let saved = bincode::serialize(&par_prime_generator_resume(cache, until)).unwrap();
+        let cache: PrimeCache<u64> = bincode::deserialize(&saved).unwrap();
+        assert_eq!(cache, uninterrupted);
+    }
+}
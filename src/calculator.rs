@@ -1,5 +1,10 @@
 use num::integer::Roots;
+use num::{Bounded, FromPrimitive, ToPrimitive};
 use rayon::prelude::*;
+use std::cmp;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, RandomState};
 
 #[inline]
 pub(crate) fn last_index<N: Roots>(n: &N, known_primes: &[N]) -> usize {
@@ -9,22 +14,46 @@ pub(crate) fn last_index<N: Roots>(n: &N, known_primes: &[N]) -> usize {
     }
 }
 
+/// Checks if n shares a factor with 30 (2 * 3 * 5) with a single modulo, in which case it's only prime if it's 2, 3 or 5.
+///
+/// Returns `None` if n is coprime with 30, which happens for about a quarter of the numbers.
+#[inline]
+fn wheel_check<N: Roots>(n: &N) -> Option<bool> {
+    // N isn't Clone, but the compiler folds these into constants anyway.
+    let two = || N::one() + N::one();
+    let three = || two() + N::one();
+    let five = || three() + two();
+    let r = n.mod_floor(&(two() * three() * five()));
+    if r.is_multiple_of(&two()) || r.is_multiple_of(&three()) || r.is_multiple_of(&five()) {
+        Some(*n == two() || *n == three() || *n == five())
+    } else {
+        None
+    }
+}
+
 /// Calculates if the number n is prime by iterating through the known primes passed in.
 ///
 /// Iteration breaks (returning true) after reaching the square root of n. It's required for the list to be ordered.
+///
+/// Multiples of 2, 3 and 5 are rejected before looking at the list, so they don't need to be in it.
 #[inline]
 pub fn is_prime<N: Roots>(n: N, known_primes: &[N]) -> bool {
     if n.is_zero() || n.is_one() {
         return false;
     };
+    if let Some(result) = wheel_check(&n) {
+        return result;
+    }
 
     let last_i = last_index(&n, known_primes);
-    !known_primes[..last_i].iter().any(|i| (n.is_multiple_of(i)))
+    !known_primes[..last_i].iter().any(|i| n.is_multiple_of(i))
 }
 
 /// Calculates if the number n is prime by iterating in parallel through only the known primes passed in.
 ///
 /// Iteration only goes up to the square root of n. It's required for the list to be ordered.
+///
+/// Multiples of 2, 3 and 5 are rejected before looking at the list, so they don't need to be in it.
 #[inline]
 pub fn par_is_prime<N>(n: N, known_primes: &[N]) -> bool
 where
@@ -33,12 +62,1002 @@ where
     if n.is_zero() || n.is_one() {
         return false;
     };
+    if let Some(result) = wheel_check(&n) {
+        return result;
+    }
 
     let last_i = last_index(&n, known_primes);
     !known_primes[..last_i]
         .par_iter()
-        .any(|i| (n.is_multiple_of(i)))
+        .any(|i| n.is_multiple_of(i))
+}
+
+/// How many divisors ahead [`prime_generator_map`](crate::generator::prime_generator_map) prefetches with [`is_prime_prefetch`] when the `prefetch` feature is enabled.
+///
+/// The CPU needs a few hundreds of cycles to bring a cache line from memory: with 8-byte divisors, 16 of them are two cache lines ahead, which covers that time for a division each without loading lines that won't be used. See the `generation` benchmark to compare different distances.
+#[cfg(feature = "prefetch")]
+pub const PREFETCH_DISTANCE: usize = 16;
+
+/// Hints the CPU to bring the value into the cache. It's a no-op on architectures other than x86_64.
+#[cfg(feature = "prefetch")]
+#[inline(always)]
+fn prefetch<T>(value: &T) {
+    #[cfg(target_arch = "x86_64")]
+    // SSE is always available on x86_64, and prefetching never faults.
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(value as *const T as *const i8);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = value;
+}
+
+/// Like [`is_prime`], but while dividing by a prime it asks the CPU to load the one `distance` positions ahead, so that scanning a list too big for the cache doesn't wait on memory.
+///
+/// Nothing is prefetched past the square root of n. The result is always the same as [`is_prime`].
+///
+/// The divisors are read in order, which the hardware prefetcher of most CPUs already detects, and a division costs more than a load: on a modern x86_64 CPU, the `generation` benchmark measures the same time with and without prefetching. It can still help on CPUs with weaker prefetchers.
+///
+/// Requires the `prefetch` feature.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(is_prime_prefetch(97, &[2, 3, 5, 7], PREFETCH_DISTANCE));
+/// assert!(!is_prime_prefetch(91, &[2, 3, 5, 7], 1));
+/// ```
+#[cfg(feature = "prefetch")]
+#[inline]
+pub fn is_prime_prefetch<N: Roots>(n: N, known_primes: &[N], distance: usize) -> bool {
+    if n.is_zero() || n.is_one() {
+        return false;
+    };
+    if let Some(result) = wheel_check(&n) {
+        return result;
+    }
+
+    let divisors = &known_primes[..last_index(&n, known_primes)];
+    !divisors.iter().enumerate().any(|(i, d)| {
+        if let Some(ahead) = divisors.get(i + distance) {
+            prefetch(ahead);
+        }
+        n.is_multiple_of(d)
+    })
+}
+
+/// Calculates if the number n is prime by consuming the divisors of an iterator, so that they don't need to be all in memory, like when they are read from a file or calculated lazily.
+///
+/// Iteration stops when a divisor is greater than the square root of n or divides it. The divisors must be ascending and reach the square root of n, like the list of [`is_prime`].
+///
+/// Multiples of 2, 3 and 5 are rejected before looking at the divisors, so they don't need to be among them.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(is_prime_iter(97, [2, 3, 5, 7, 11].into_iter()));
+/// assert!(!is_prime_iter(91, [2, 3, 5, 7, 11].into_iter()));
+/// assert!(is_prime_iter(97, 2..)); // Every number works too, only slower.
+/// ```
+pub fn is_prime_iter<N: Roots, I: Iterator<Item = N>>(n: N, divisors: I) -> bool {
+    if n.is_zero() || n.is_one() {
+        return false;
+    };
+    if let Some(result) = wheel_check(&n) {
+        return result;
+    }
+
+    let sqr = n.sqrt();
+    !divisors
+        .take_while(|d| *d <= sqr)
+        .any(|d| n.is_multiple_of(&d))
+}
+
+/// The error returned by [`is_prime_result`] and [`prime_pi`] when the known primes don't reach far enough to give an exact answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientCache<N> {
+    /// For [`is_prime_result`], the square root of the number, and for [`prime_pi`], the number before the bound. A list can't say it's complete after its last prime, so it must contain a prime greater than or equal to this to decide: by Bertrand's postulate, the primes up to twice it are enough.
+    pub needed_up_to: N,
 }
+
+impl<N: fmt::Display> fmt::Display for InsufficientCache<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the known primes must reach {} to decide",
+            self.needed_up_to
+        )
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for InsufficientCache<N> {}
+
+/// Calculates if the number n is prime like [`is_prime`], but returns an error instead of guessing when the known primes don't reach the square root of n.
+///
+/// If one of the known primes divides n, it's composite even if the list is too short. It's required for the list to be ordered.
+///
+/// Like in [`is_prime`], multiples of 2, 3 and 5 are rejected before looking at the list, so they don't need to be in it.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(is_prime_result(47, &[2, 3, 5, 7]), Ok(true));
+/// assert_eq!(is_prime_result(25, &[]), Ok(false));
+/// assert_eq!(is_prime_result(49, &[2, 3, 5]), Err(InsufficientCache { needed_up_to: 7 }));
+/// assert_eq!(is_prime_result(50, &[2, 3, 5]), Ok(false));
+/// ```
+pub fn is_prime_result<N: Roots + Copy>(
+    n: N,
+    known_primes: &[N],
+) -> Result<bool, InsufficientCache<N>> {
+    if n.is_zero() || n.is_one() {
+        return Ok(false);
+    }
+    if let Some(result) = wheel_check(&n) {
+        return Ok(result);
+    }
+    let last_i = last_index(&n, known_primes);
+    if known_primes[..last_i].iter().any(|i| n.is_multiple_of(i)) {
+        return Ok(false);
+    }
+    let sqr = n.sqrt();
+    let seven = N::one() + N::one() + N::one() + N::one() + N::one() + N::one() + N::one();
+    match known_primes.last() {
+        Some(&p) if p >= sqr => Ok(true),
+        // The wheel already checked every prime less than 7.
+        _ if sqr < seven => Ok(true),
+        _ => Err(InsufficientCache { needed_up_to: sqr }),
+    }
+}
+
+/// Calculates if the number n is prime like [`is_prime`], but returns `None` instead of guessing when the known primes don't reach the square root of n.
+///
+/// It's [`is_prime_result`] without the error: if one of the known primes divides n, it's composite even if the list is too short. It's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(checked_is_prime(47, &[2, 3, 5, 7]), Some(true));
+/// assert_eq!(checked_is_prime(49, &[2, 3, 5]), None);
+/// assert_eq!(checked_is_prime(50, &[2, 3, 5]), Some(false));
+/// ```
+pub fn checked_is_prime<N: Roots + Copy>(n: N, known_primes: &[N]) -> Option<bool> {
+    is_prime_result(n, known_primes).ok()
+}
+
+/// Counts the primes less than n, π(n - 1), by searching n in the known primes passed in.
+///
+/// It's required for the list to be ordered and to contain every prime up to its last element. If there can be primes less than n after the last element, an error is returned instead of a count that could be too small: the list must reach n - 1.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(prime_pi(10, &[2, 3, 5, 7, 11]), Ok(4));
+/// assert_eq!(prime_pi(12, &[2, 3, 5, 7, 11]), Ok(5));
+/// assert_eq!(prime_pi(14, &[2, 3, 5, 7, 11]), Err(InsufficientCache { needed_up_to: 13 }));
+/// ```
+pub fn prime_pi<N: Roots + FromPrimitive + Copy>(
+    n: N,
+    known_primes: &[N],
+) -> Result<N, InsufficientCache<N>> {
+    let two = N::one() + N::one();
+    if n <= two {
+        return Ok(N::zero());
+    }
+    let needed_up_to = n - N::one();
+    match known_primes.last() {
+        Some(&p) if p >= needed_up_to => {
+            Ok(N::from_usize(known_primes.partition_point(|&p| p < n)).unwrap())
+        }
+        _ => Err(InsufficientCache { needed_up_to }),
+    }
+}
+
+/// Returns the position of p in the sequence of primes, starting from 1, by searching it in the known primes passed in.
+///
+/// Returns `None` if p isn't in the list. It's required for the list to be ordered and to contain every prime up to p.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(prime_index(13, &[2, 3, 5, 7, 11, 13]), Some(6));
+/// assert_eq!(prime_index(12, &[2, 3, 5, 7, 11, 13]), None);
+/// ```
+#[inline]
+pub fn prime_index<N: Ord>(p: N, known_primes: &[N]) -> Option<usize> {
+    known_primes.binary_search(&p).ok().map(|i| i + 1)
+}
+
+/// The default trial_cutoff of [`ignorant_is_prime_cutoff`], used by [`ignorant_is_prime`].
+pub const TRIAL_CUTOFF: u64 = 1000;
+
+/// Calculates if the number n is prime by iterating through the known primes passed in, and then through every odd number after the last one, up to [`TRIAL_CUTOFF`].
+///
+/// Unlike [`is_prime`], the list doesn't need to reach the square root of n: it can even be empty. It's required for the list to be ordered.
+///
+/// See [`ignorant_is_prime_cutoff`] for what happens after the cutoff.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(ignorant_is_prime(97, &[]));
+/// assert!(!ignorant_is_prime(91, &[2, 3])); // 7 * 13
+/// assert!(ignorant_is_prime(97, &[2, 3, 5, 7, 11]));
+/// ```
+#[inline]
+pub fn ignorant_is_prime<N: Roots + FromPrimitive + ToPrimitive + Copy>(
+    n: N,
+    known_primes: &[N],
+) -> bool {
+    ignorant_is_prime_cutoff(n, known_primes, TRIAL_CUTOFF)
+}
+
+/// Calculates if the number n is prime by iterating through the known primes passed in, and then through every odd number after the last one, up to trial_cutoff.
+///
+/// If the divisors reach the square root of n before the cutoff, the result is known. Otherwise, most composite numbers have already been rejected, and the rest is left to [`mont_is_prime`], which is much faster than dividing up to the square root of a big number. Numbers that don't fit in a u64 are always divided up to their square root.
+///
+/// Unlike [`is_prime`], the list doesn't need to reach the square root of n: it can even be empty. It's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// // Only trial division.
+/// assert!(ignorant_is_prime_cutoff(1_000_000_007u64, &[], u64::MAX));
+/// // Only Miller-Rabin.
+/// assert!(ignorant_is_prime_cutoff(1_000_000_007u64, &[], 0));
+/// ```
+pub fn ignorant_is_prime_cutoff<N: Roots + FromPrimitive + ToPrimitive + Copy>(
+    n: N,
+    known_primes: &[N],
+    trial_cutoff: u64,
+) -> bool {
+    let two = N::one() + N::one();
+    if n < two {
+        return false;
+    }
+
+    let sqr = n.sqrt();
+    let n_u64 = n.to_u64();
+    let limit = match (n_u64, N::from_u64(trial_cutoff)) {
+        (Some(_), Some(cutoff)) => cmp::min(sqr, cutoff),
+        _ => sqr,
+    };
+
+    let last_i = known_primes.partition_point(|&p| p <= limit);
+    if known_primes[..last_i].iter().any(|i| n.is_multiple_of(i)) {
+        return false;
+    }
+    // If not, the known primes already reached the limit.
+    if last_i == known_primes.len() {
+        let mut divisor = match known_primes.last() {
+            Some(&p) if p >= two => {
+                if p.is_even() {
+                    p + N::one()
+                } else {
+                    p + two
+                }
+            }
+            _ => {
+                if n.is_multiple_of(&two) {
+                    return n == two;
+                }
+                two + N::one()
+            }
+        };
+        while divisor <= limit {
+            if n.is_multiple_of(&divisor) {
+                return false;
+            }
+            divisor = divisor + two;
+        }
+    }
+
+    match n_u64 {
+        Some(n) if limit < sqr => mont_is_prime(n),
+        _ => true,
+    }
+}
+
+/// The reason a list of primes is invalid, returned by [`validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError<N> {
+    /// The number at the index isn't greater than the one before it.
+    Unordered { index: usize, value: N },
+    /// The number at the index isn't prime, according to the primes before it.
+    Composite { index: usize, value: N },
+}
+
+impl<N> ValidationError<N> {
+    /// The index of the first invalid number.
+    pub fn index(&self) -> usize {
+        match self {
+            ValidationError::Unordered { index, .. } => *index,
+            ValidationError::Composite { index, .. } => *index,
+        }
+    }
+}
+
+impl<N: fmt::Display> fmt::Display for ValidationError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Unordered { index, value } => write!(
+                f,
+                "{} at index {} isn't greater than the number before it",
+                value, index
+            ),
+            ValidationError::Composite { index, value } => {
+                write!(f, "{} at index {} isn't prime", value, index)
+            }
+        }
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for ValidationError<N> {}
+
+/// Parallelely checks that the list is strictly increasing and that every number in it is prime, using the primes before it as divisors.
+///
+/// Primes missing from the list can't be detected, but every number before the square root of the last one must be there for the check to be complete.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(validate(&[2, 3, 5, 7, 11]), Ok(()));
+/// assert_eq!(validate(&[2, 3, 5, 7, 9]), Err(ValidationError::Composite { index: 4, value: 9 }));
+/// assert_eq!(validate(&[2, 3, 7, 5]), Err(ValidationError::Unordered { index: 3, value: 5 }));
+/// ```
+pub fn validate<N: Roots + Copy + Send + Sync>(primes: &[N]) -> Result<(), ValidationError<N>> {
+    if let Some(i) = primes.par_windows(2).position_first(|w| w[0] >= w[1]) {
+        return Err(ValidationError::Unordered {
+            index: i + 1,
+            value: primes[i + 1],
+        });
+    }
+    if let Some((index, &value)) = primes
+        .par_iter()
+        .enumerate()
+        .find_first(|(i, p)| !is_prime(**p, &primes[..*i]))
+    {
+        return Err(ValidationError::Composite { index, value });
+    }
+    Ok(())
+}
+
+/// The disagreement found by [`verify_primes`] between a list of primes and an independent primality test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationError<N> {
+    /// The number at the index isn't greater than the one before it.
+    Unordered { index: usize, value: N },
+    /// The number at the index isn't prime.
+    Composite { index: usize, value: N },
+    /// The number is prime, but it's missing from the list.
+    Missing { value: N },
+}
+
+impl<N: fmt::Display> fmt::Display for VerificationError<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::Unordered { index, value } => write!(
+                f,
+                "{} at index {} isn't greater than the number before it",
+                value, index
+            ),
+            VerificationError::Composite { index, value } => {
+                write!(f, "{} at index {} isn't prime", value, index)
+            }
+            VerificationError::Missing { value } => {
+                write!(f, "{} is prime, but it's missing", value)
+            }
+        }
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for VerificationError<N> {}
+
+/// Returns a nonzero seed for [`xorshift64`] that changes between runs.
+///
+/// Any such seed is enough for the random checks of this module, so it's taken from the random keys of the hash maps.
+fn random_seed<T: Hash>(key: T) -> u64 {
+    RandomState::new().hash_one(key) | 1
+}
+
+/// Advances the state of a xorshift64 generator, which must not be 0, and returns it.
+fn xorshift64(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+/// Returns the indices from 0 to len to check: all of them if they are at most samples, otherwise samples random ones.
+fn sample_indices(len: usize, samples: usize) -> Vec<usize> {
+    if samples >= len {
+        return (0..len).collect();
+    }
+    let mut state = random_seed(len);
+    (0..samples)
+        .map(|_| (xorshift64(&mut state) % len as u64) as usize)
+        .collect()
+}
+
+/// Checks the list of primes less than until against [`ignorant_is_prime`], which doesn't depend on how the list was calculated.
+///
+/// For every index checked, the number must be prime and greater than the one before it, and no number between it and the next one (or until, for the last) must be prime. The numbers before the first one are always checked.
+///
+/// If the list has at most samples primes, every index is checked, which proves the list is exactly the primes less than until. Otherwise, samples random indices are checked.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(verify_primes(&[2, 3, 5, 7], 10, 100), Ok(()));
+/// assert_eq!(verify_primes(&[2, 3, 5, 7, 9], 10, 100), Err(VerificationError::Composite { index: 4, value: 9 }));
+/// assert_eq!(verify_primes(&[2, 3, 7], 10, 100), Err(VerificationError::Missing { value: 5 }));
+/// assert_eq!(verify_primes(&[2, 3, 5], 10, 100), Err(VerificationError::Missing { value: 7 }));
+/// ```
+pub fn verify_primes<N: Roots + FromPrimitive + ToPrimitive + Copy>(
+    primes: &[N],
+    until: N,
+    samples: usize,
+) -> Result<(), VerificationError<N>> {
+    let missing = |from: N, to: N| {
+        num::range(from, to)
+            .find(|&n| ignorant_is_prime(n, &[]))
+            .map_or(Ok(()), |value| Err(VerificationError::Missing { value }))
+    };
+
+    missing(N::zero(), primes.first().cloned().unwrap_or(until))?;
+    for index in sample_indices(primes.len(), samples) {
+        let value = primes[index];
+        if index > 0 && primes[index - 1] >= value {
+            return Err(VerificationError::Unordered { index, value });
+        }
+        if !ignorant_is_prime(value, &[]) {
+            return Err(VerificationError::Composite { index, value });
+        }
+        let next = primes.get(index + 1).cloned().unwrap_or(until);
+        missing(value + N::one(), next)?;
+    }
+    Ok(())
+}
+
+/// Parallelely checks if every candidate is in the known primes passed in, returning the results in the same order.
+///
+/// It's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(par_contains_all(&[7, 8, 2, 13], &[2, 3, 5, 7, 11]), vec![true, false, true, false]);
+/// ```
+pub fn par_contains_all<N: Ord + Sync>(candidates: &[N], known_primes: &[N]) -> Vec<bool> {
+    candidates
+        .par_iter()
+        .map(|n| known_primes.binary_search(n).is_ok())
+        .collect()
+}
+
+/// Returns the first index where two lists of primes differ, along with the values they have there, or `None` if they're equal.
+///
+/// If one list is a prefix of the other, the shorter one has `None` at the index.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(diff_primes(&[2, 3, 5, 7], &[2, 3, 5, 7]), None);
+/// assert_eq!(diff_primes(&[2, 3, 5, 7], &[2, 3, 5, 9]), Some((3, Some(7), Some(9))));
+/// assert_eq!(diff_primes(&[2, 3, 5, 7], &[2, 3]), Some((2, Some(5), None)));
+/// ```
+pub fn diff_primes<N: PartialEq + Copy>(a: &[N], b: &[N]) -> Option<(usize, Option<N>, Option<N>)> {
+    (0..a.len().max(b.len()))
+        .map(|i| (i, a.get(i).cloned(), b.get(i).cloned()))
+        .find(|(_, x, y)| x != y)
+}
+
+/// Calculates base raised to exp, modulo modulus.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(mod_pow(4, 13, 497), 445);
+/// assert_eq!(mod_pow(2, 64, u64::MAX), 1);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if modulus is 0.
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let modulus = modulus as u128;
+    let mut base = base as u128 % modulus;
+    let mut result = 1 % modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    result as u64
+}
+
+/// These witnesses are enough to make Miller-Rabin deterministic for every 64-bit number, and up to [`MILLER_RABIN_LIMIT`].
+pub const MILLER_RABIN_WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+/// The first strong pseudoprime to the first witnesses, paired with how many witnesses there are: every number below it is classified correctly by them.
+const WITNESS_BOUNDS: [(u64, usize); 8] = [
+    (2047, 1),
+    (1_373_653, 2),
+    (25_326_001, 3),
+    (3_215_031_751, 4),
+    (2_152_302_898_747, 5),
+    (3_474_749_660_383, 6),
+    (341_550_071_728_321, 7),
+    (3_825_123_056_546_413_051, 9),
+];
+
+/// Returns the fewest witnesses that make Miller-Rabin deterministic for n, so that smaller numbers need fewer exponentiations.
+fn witnesses_for(n: u64) -> &'static [u64] {
+    let len = WITNESS_BOUNDS
+        .iter()
+        .find(|&&(bound, _)| n < bound)
+        .map_or(MILLER_RABIN_WITNESSES.len(), |&(_, len)| len);
+    &MILLER_RABIN_WITNESSES[..len]
+}
+
+/// Handles the numbers that are divisible by one of the witnesses, returning `None` for the others.
+fn witness_divisibility(n: u64) -> Option<bool> {
+    if n < 2 {
+        return Some(false);
+    }
+    MILLER_RABIN_WITNESSES
+        .iter()
+        .find(|&&p| n.is_multiple_of(p))
+        .map(|&p| n == p)
+}
+
+/// Calculates if the number n is prime using the Miller-Rabin test with a set of witnesses that makes it exact for every u64.
+///
+/// The smaller n is, the fewer witnesses are needed: below 2047 the base 2 alone is enough.
+///
+/// Unlike [`is_prime`], no list of known primes is needed.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(deterministic_is_prime(18_446_744_073_709_551_557));
+/// assert!(!deterministic_is_prime(3_215_031_751)); // A strong pseudoprime to the bases 2, 3, 5 and 7.
+/// ```
+pub fn deterministic_is_prime(n: u64) -> bool {
+    if let Some(result) = witness_divisibility(n) {
+        return result;
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    witnesses_for(n).iter().all(|&a| {
+        let mut x = mod_pow(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        (1..s).any(|_| {
+            x = (x as u128 * x as u128 % n as u128) as u64;
+            x == n - 1
+        })
+    })
+}
+
+/// Arithmetic modulo an odd number in Montgomery form, with R = 2^64.
+struct Montgomery {
+    n: u64,
+    /// The inverse of n modulo R.
+    n_inv: u64,
+    /// R modulo n, which is 1 in Montgomery form.
+    one: u64,
+}
+
+/// Calculates the inverse of the odd number n modulo 2^64.
+fn inverse_mod_2_64(n: u64) -> u64 {
+    // Newton's method doubles the number of correct bits every iteration, starting from 3.
+    let mut n_inv = n;
+    for _ in 0..5 {
+        n_inv = n_inv.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(n_inv)));
+    }
+    n_inv
+}
+
+impl Montgomery {
+    fn new(n: u64) -> Self {
+        Montgomery {
+            n,
+            n_inv: inverse_mod_2_64(n),
+            one: ((1u128 << 64) % n as u128) as u64,
+        }
+    }
+
+    fn to_mont(&self, a: u64) -> u64 {
+        (((a as u128) << 64) % self.n as u128) as u64
+    }
+
+    /// Calculates t / R modulo n.
+    fn reduce(&self, t: u128) -> u64 {
+        let m = (t as u64).wrapping_mul(self.n_inv);
+        let mn = ((m as u128 * self.n as u128) >> 64) as u64;
+        let t = (t >> 64) as u64;
+        // t and m * n have the same lower half, so only the upper halves need to be subtracted.
+        if t < mn {
+            t.wrapping_sub(mn).wrapping_add(self.n)
+        } else {
+            t - mn
+        }
+    }
+
+    fn mul(&self, a: u64, b: u64) -> u64 {
+        self.reduce(a as u128 * b as u128)
+    }
+
+    fn pow(&self, mut base: u64, mut exp: u64) -> u64 {
+        let mut result = self.one;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base);
+            }
+            base = self.mul(base, base);
+            exp >>= 1;
+        }
+        result
+    }
+}
+
+/// Calculates if the number n is prime like [`deterministic_is_prime`], but with the modular exponentiations done in Montgomery form.
+///
+/// This avoids a 128-bit division for every multiplication, which makes it faster when testing many numbers.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(mont_is_prime(18_446_744_073_709_551_557));
+/// assert!(!mont_is_prime(3_215_031_751));
+/// ```
+pub fn mont_is_prime(n: u64) -> bool {
+    if let Some(result) = witness_divisibility(n) {
+        return result;
+    }
+
+    let mont = Montgomery::new(n);
+    let minus_one = n - mont.one;
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    witnesses_for(n).iter().all(|&a| {
+        let mut x = mont.pow(mont.to_mont(a), d);
+        if x == mont.one || x == minus_one {
+            return true;
+        }
+        (1..s).any(|_| {
+            x = mont.mul(x, x);
+            x == minus_one
+        })
+    })
+}
+
+/// Returns the prime closest to n, which is n itself if it's prime. If two primes are equally close, the smaller one is returned.
+///
+/// No list of known primes is needed, as the neighbours are found with [`checked_next_prime`](crate::generator::checked_next_prime) and [`prev_prime`](crate::generator::prev_prime), which check the candidates with [`mont_is_prime`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(nearest_prime(100), 101);
+/// assert_eq!(nearest_prime(99), 97);
+/// assert_eq!(nearest_prime(0), 2);
+/// ```
+pub fn nearest_prime(n: u64) -> u64 {
+    if n <= 2 {
+        return 2;
+    }
+    if mont_is_prime(n) {
+        return n;
+    }
+    // There's always a prime below n, but the one above could not fit.
+    let prev = crate::generator::prev_prime(n, &[]).unwrap();
+    match crate::generator::checked_next_prime(n, &[]) {
+        Some(next) if next - n < n - prev => next,
+        _ => prev,
+    }
+}
+
+/// The first strong pseudoprime to all the [`MILLER_RABIN_WITNESSES`] (Sorenson and Webster, 2015): below it, [`is_probable_prime`] is exact.
+pub const MILLER_RABIN_LIMIT: u128 = 318_665_857_834_031_151_167_461;
+
+/// Calculates (a + b) % m without overflowing, for a and b less than m.
+fn add_mod_u128(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+/// Calculates a * b % m without overflowing, by doubling and adding, for a and b less than m.
+fn mul_mod_u128(mut a: u128, mut b: u128, m: u128) -> u128 {
+    let mut result = 0;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod_u128(result, a, m);
+        }
+        a = add_mod_u128(a, a, m);
+        b >>= 1;
+    }
+    result
+}
+
+/// Checks if a is a witness of the compositeness of n, where n - 1 = d * 2^s with d odd.
+fn is_witness_u128(a: u128, n: u128, d: u128, s: u32) -> bool {
+    let mut x = 1;
+    let (mut base, mut exp) = (a % n, d);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            x = mul_mod_u128(x, base, n);
+        }
+        base = mul_mod_u128(base, base, n);
+        exp >>= 1;
+    }
+    if x == 1 || x == n - 1 {
+        return false;
+    }
+    !(1..s).any(|_| {
+        x = mul_mod_u128(x, x, n);
+        x == n - 1
+    })
+}
+
+/// Calculates if the number n is prime with the Miller-Rabin test, without any list of known primes.
+///
+/// - Numbers that fit in a u64 are checked with [`mont_is_prime`], which is exact.
+/// - Up to [`MILLER_RABIN_LIMIT`], the [`MILLER_RABIN_WITNESSES`] are exact too.
+/// - Above it, rounds random witnesses are tried after them: a composite number passes each one with a probability of at most 1/4.
+///
+/// Perfect squares are rejected before the test, as the squares of some primes fool many witnesses.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert!(is_probable_prime(18_446_744_073_709_551_557, 0));
+/// assert!(is_probable_prime((1 << 89) - 1, 10)); // A Mersenne prime.
+/// assert!(!is_probable_prime(MILLER_RABIN_LIMIT, 10));
+/// ```
+pub fn is_probable_prime(n: u128, rounds: usize) -> bool {
+    if let Ok(n) = u64::try_from(n) {
+        return mont_is_prime(n);
+    }
+    if MILLER_RABIN_WITNESSES
+        .iter()
+        .any(|&p| n.is_multiple_of(p as u128))
+        || n.isqrt().pow(2) == n
+    {
+        return false;
+    }
+
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+    if MILLER_RABIN_WITNESSES
+        .iter()
+        .any(|&a| is_witness_u128(a as u128, n, d, s))
+    {
+        return false;
+    }
+    if n < MILLER_RABIN_LIMIT {
+        return true;
+    }
+
+    let mut state = random_seed(n);
+    let mut next = || xorshift64(&mut state);
+    (0..rounds).all(|_| {
+        // A random witness between 2 and n - 2.
+        let a = (((next() as u128) << 64) | next() as u128) % (n - 3) + 2;
+        !is_witness_u128(a, n, d, s)
+    })
+}
+
+/// Primes between consecutive powers of two, each as far as possible from both: the first is between 2^5 and 2^6, the last between 2^30 and 2^31.
+///
+/// Using them as hash table sizes keeps the load factor steady when the table doubles.
+pub const TABLE_SIZE_LADDER: [usize; 26] = [
+    53, 97, 193, 389, 769, 1543, 3079, 6151, 12289, 24593, 49157, 98317, 196613, 393241, 786433,
+    1572869, 3145739, 6291469, 12582917, 25165843, 50331653, 100663319, 201326611, 402653189,
+    805306457, 1610612741,
+];
+
+/// Returns the smallest prime greater than or equal to min_capacity, to be used as the size of a hash table.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(prime_table_size(1000), 1009);
+/// assert_eq!(prime_table_size(1009), 1009);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if there is no such prime that fits in a usize.
+pub fn prime_table_size(min_capacity: usize) -> usize {
+    if min_capacity <= 2 {
+        return 2;
+    }
+    crate::generator::checked_next_prime(min_capacity as u64 - 1, &[])
+        .and_then(|p| usize::try_from(p).ok())
+        .expect("there is no prime that big")
+}
+
+/// Returns the first element of [`TABLE_SIZE_LADDER`] greater than or equal to min_capacity without searching for it, or `None` if they're all smaller.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(ladder_table_size(0), Some(53));
+/// assert_eq!(ladder_table_size(1000), Some(1543));
+/// assert_eq!(ladder_table_size(1 << 31), None);
+/// ```
+pub fn ladder_table_size(min_capacity: usize) -> Option<usize> {
+    if min_capacity <= TABLE_SIZE_LADDER[0] {
+        return Some(TABLE_SIZE_LADDER[0]);
+    }
+    // The element between 2^k and 2^(k+1) is at index k - 5.
+    let i = min_capacity.ilog2() as usize - 5;
+    TABLE_SIZE_LADDER
+        .get(i)
+        .filter(|&&p| p >= min_capacity)
+        .or_else(|| TABLE_SIZE_LADDER.get(i + 1))
+        .cloned()
+}
+
+/// A list of known primes with what's needed to check if a number is divisible by each of them with a multiplication instead of a division.
+///
+/// For an odd divisor d, n is a multiple of d if and only if n times the inverse of d modulo 2^64 is at most (2^64 - 1) / d, as the multiples of d are exactly the numbers that the multiplication maps to the smallest values.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// let divisors = FastDivisors::new(&[2, 3, 5, 7, 11, 13]);
+/// assert!(is_prime_fast(97, &divisors));
+/// assert!(!is_prime_fast(91, &divisors)); // 7 * 13
+/// ```
+#[derive(Debug, Clone)]
+pub struct FastDivisors {
+    primes: Vec<u64>,
+    /// The inverse of every odd prime modulo 2^64. The one of 2 is unused.
+    inverses: Vec<u64>,
+    /// The greatest value that a multiple of every prime is mapped to.
+    limits: Vec<u64>,
+}
+
+impl FastDivisors {
+    /// Precomputes the inverses of the known primes passed in. It's required for the list to be ordered.
+    pub fn new(known_primes: &[u64]) -> Self {
+        FastDivisors {
+            primes: known_primes.to_vec(),
+            inverses: known_primes
+                .iter()
+                .map(|&p| if p % 2 == 1 { inverse_mod_2_64(p) } else { 0 })
+                .collect(),
+            limits: known_primes.iter().map(|&p| u64::MAX / p).collect(),
+        }
+    }
+
+    /// The known primes.
+    pub fn primes(&self) -> &[u64] {
+        &self.primes
+    }
+
+    fn divides(&self, i: usize, n: u64) -> bool {
+        if self.primes[i] == 2 {
+            n.is_multiple_of(2)
+        } else {
+            n.wrapping_mul(self.inverses[i]) <= self.limits[i]
+        }
+    }
+}
+
+/// Calculates if the number n is prime like [`is_prime`], but checking the divisibility by the known primes with multiplications precomputed by [`FastDivisors`].
+///
+/// Iteration breaks (returning true) after reaching the square root of n. Like in [`is_prime`], multiples of 2, 3 and 5 are rejected before looking at the list, so they don't need to be in it.
+pub fn is_prime_fast(n: u64, divisors: &FastDivisors) -> bool {
+    if n < 2 {
+        return false;
+    }
+    if let Some(result) = wheel_check(&n) {
+        return result;
+    }
+    let last_i = last_index(&n, &divisors.primes);
+    !(0..last_i).any(|i| divisors.divides(i, n))
+}
+
+/// The largest prime that fits in every primitive integer type, from the narrowest to the widest, unsigned and signed.
+const LARGEST_PRIMES: [u128; 10] = [
+    251,
+    127,
+    65_521,
+    32_749,
+    4_294_967_291,
+    2_147_483_647,
+    18_446_744_073_709_551_557,
+    9_223_372_036_854_775_783,
+    340_282_366_920_938_463_463_374_607_431_768_211_297,
+    170_141_183_460_469_231_731_687_303_715_884_105_727,
+];
+
+/// Returns the largest prime less than or equal to n by checking every odd number below it with [`ignorant_is_prime`].
+fn largest_prime_below<N: Roots + FromPrimitive + ToPrimitive + Copy>(n: N) -> N {
+    let two = N::one() + N::one();
+    let mut candidate = if n.is_even() { n - N::one() } else { n };
+    while candidate > two && !ignorant_is_prime(candidate, &[]) {
+        candidate = candidate - two;
+    }
+    candidate
+}
+
+/// Returns the largest prime that fits in N.
+///
+/// The result is already known for every primitive integer type. For other types, the numbers below the maximum value are checked one by one with [`ignorant_is_prime`], which only stays fast while they fit in a u64.
+///
+/// # Examples
+///
+/// ```
+/// use prime::calculator::*;
+///
+/// assert_eq!(largest_prime_in_type::<u32>(), 4_294_967_291);
+/// assert_eq!(largest_prime_in_type::<u64>(), 18_446_744_073_709_551_557);
+/// assert_eq!(largest_prime_in_type::<i32>(), 2_147_483_647);
+/// ```
+pub fn largest_prime_in_type<N: Roots + Bounded + FromPrimitive + ToPrimitive + Copy>() -> N {
+    let max = N::max_value();
+    let max_u128 = max.to_u128();
+    LARGEST_PRIMES
+        .iter()
+        .find(|&&p| {
+            // The largest prime of a type is more than half its maximum value, so the maximum value identifies it.
+            max_u128.is_some_and(|m| p <= m && p > m / 2)
+        })
+        .and_then(|&p| N::from_u128(p))
+        .unwrap_or_else(|| largest_prime_below(max))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -69,4 +1088,513 @@ mod tests {
         assert!(par_is_prime(7, &[2, 3, 5, 7]));
         assert!(!par_is_prime(9, &[2, 3, 5, 7]));
     }
+
+    #[test]
+    fn prime_index_test() {
+        assert_eq!(prime_index(2, &[2, 3, 5, 7, 11, 13]), Some(1));
+        assert_eq!(prime_index(13, &[2, 3, 5, 7, 11, 13]), Some(6));
+        assert_eq!(prime_index(12, &[2, 3, 5, 7, 11, 13]), None);
+        assert_eq!(prime_index(17, &[2, 3, 5, 7, 11, 13]), None);
+        assert_eq!(prime_index(2, &[]), None);
+    }
+
+    #[test]
+    fn ignorant_prime_test() {
+        assert!(!ignorant_is_prime(0, &[]));
+        assert!(!ignorant_is_prime(1, &[]));
+        assert!(ignorant_is_prime(2, &[]));
+        assert!(ignorant_is_prime(3, &[]));
+        assert!(!ignorant_is_prime(4, &[]));
+        assert!(ignorant_is_prime(5, &[]));
+        assert!(!ignorant_is_prime(9, &[]));
+        assert!(!ignorant_is_prime(25, &[2]));
+        assert!(!ignorant_is_prime(49, &[2, 3]));
+        assert!(ignorant_is_prime(2, &[2, 3, 5, 7]));
+        assert!(!ignorant_is_prime(121, &[2, 3, 5, 7]));
+        assert!(ignorant_is_prime(4_294_967_291u64, &[2, 3, 5, 7]));
+        assert!(!ignorant_is_prime(4_294_967_297u64, &[])); // 641 * 6700417
+    }
+
+    #[test]
+    fn validate_test() {
+        assert_eq!(validate::<u32>(&[]), Ok(()));
+        assert_eq!(validate(&[2, 3, 5, 7, 11, 13]), Ok(()));
+        assert_eq!(
+            validate(&[1, 2, 3]),
+            Err(ValidationError::Composite { index: 0, value: 1 })
+        );
+        assert_eq!(
+            validate(&[2, 3, 5, 7, 11, 13, 15]),
+            Err(ValidationError::Composite {
+                index: 6,
+                value: 15
+            })
+        );
+        assert_eq!(
+            validate(&[2, 3, 3, 5]),
+            Err(ValidationError::Unordered { index: 2, value: 3 })
+        );
+    }
+
+    #[test]
+    fn par_contains_all_test() {
+        let primes = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29];
+        let candidates: Vec<u32> = (0..40).rev().chain(0..40).collect();
+        assert_eq!(
+            par_contains_all(&candidates, &primes),
+            candidates
+                .iter()
+                .map(|n| primes.binary_search(n).is_ok())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(par_contains_all(&[], &primes), Vec::<bool>::new());
+        assert_eq!(par_contains_all(&[2, 3], &[]), vec![false, false]);
+    }
+
+    #[test]
+    fn mod_pow_test() {
+        assert_eq!(mod_pow(0, 0, 7), 1);
+        assert_eq!(mod_pow(5, 0, 1), 0);
+        assert_eq!(mod_pow(3, 4, 100), 81);
+        assert_eq!(mod_pow(10, 6, 7), 1);
+        assert_eq!(mod_pow(u64::MAX - 1, 2, u64::MAX), 1);
+    }
+
+    #[test]
+    fn deterministic_prime_test() {
+        let primes = crate::generator::prime_generator(10_000u64, Vec::new(), 0);
+        for n in 0..10_000 {
+            let expected = primes.binary_search(&n).is_ok();
+            assert_eq!(deterministic_is_prime(n), expected, "{}", n);
+            assert_eq!(mont_is_prime(n), expected, "{}", n);
+        }
+
+        // Carmichael numbers and strong pseudoprimes.
+        for n in [
+            561,
+            1_105,
+            1_729,
+            2_047,
+            1_373_653,
+            25_326_001,
+            3_215_031_751,
+        ] {
+            assert!(!deterministic_is_prime(n));
+            assert!(!mont_is_prime(n));
+        }
+        assert!(!deterministic_is_prime(3_825_123_056_546_413_051));
+        assert!(!mont_is_prime(3_825_123_056_546_413_051));
+
+        for n in [4_294_967_291, 18_446_744_073_709_551_557] {
+            assert!(deterministic_is_prime(n));
+            assert!(mont_is_prime(n));
+        }
+        assert!(!deterministic_is_prime(u64::MAX));
+        assert!(!mont_is_prime(u64::MAX));
+    }
+
+    #[test]
+    fn mont_matches_deterministic_test() {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        for _ in 0..20_000 {
+            // Odd numbers make the comparison more interesting.
+            let n = xorshift64(&mut state) | 1;
+            assert_eq!(mont_is_prime(n), deterministic_is_prime(n), "{}", n);
+        }
+    }
+
+    #[test]
+    fn wheel_test() {
+        for primes in [&[][..], &[2, 3, 5, 7][..]] {
+            assert!(is_prime(2, primes));
+            assert!(is_prime(3, primes));
+            assert!(is_prime(5, primes));
+            assert!(!is_prime(4, primes));
+            assert!(!is_prime(9, primes));
+            assert!(!is_prime(25, primes));
+            assert!(!is_prime(30, primes));
+            assert!(!is_prime(35, primes));
+            assert!(par_is_prime(5, primes));
+            assert!(!par_is_prime(25, primes));
+        }
+        assert!(!is_prime(49, &[2, 3, 5, 7]));
+        assert!(is_prime(31, &[2, 3, 5, 7]));
+        assert!(is_prime(7, &[2, 3, 5, 7]));
+
+        // Negative numbers are rejected by the wheel only if they share a factor with 30.
+        assert!(!is_prime(-4, &[]));
+        assert!(!is_prime(-2, &[]));
+
+        let primes = crate::generator::prime_generator(10_000u32, Vec::new(), 0);
+        assert!((0..10_000).all(|n| is_prime(n, &primes) == primes.binary_search(&n).is_ok()));
+    }
+
+    #[test]
+    fn nearest_prime_test() {
+        assert_eq!(nearest_prime(0), 2);
+        assert_eq!(nearest_prime(1), 2);
+        assert_eq!(nearest_prime(2), 2);
+        assert_eq!(nearest_prime(3), 3);
+        assert_eq!(nearest_prime(4), 3); // 3 and 5 are equally close.
+        assert_eq!(nearest_prime(99), 97);
+        assert_eq!(nearest_prime(100), 101);
+        assert_eq!(nearest_prime(101), 101);
+        assert_eq!(nearest_prime(120), 113); // 113 and 127 are equally close.
+        assert_eq!(nearest_prime(u64::MAX), 18_446_744_073_709_551_557);
+    }
+
+    #[test]
+    fn table_size_test() {
+        assert_eq!(prime_table_size(0), 2);
+        assert_eq!(prime_table_size(3), 3);
+        assert_eq!(prime_table_size(4), 5);
+        assert_eq!(prime_table_size(1000), 1009);
+        assert_eq!(prime_table_size(1 << 20), 1_048_583);
+
+        for (k, &p) in TABLE_SIZE_LADDER.iter().enumerate() {
+            assert!(mont_is_prime(p as u64));
+            assert!(p > 1 << (k + 5) && p < 1 << (k + 6));
+            assert_eq!(ladder_table_size(p), Some(p));
+            assert_eq!(ladder_table_size(p - 1), Some(p));
+            assert_eq!(ladder_table_size(1 << (k + 5)), Some(p));
+        }
+        assert_eq!(ladder_table_size(1000), Some(1543));
+        assert_eq!(ladder_table_size(1544), Some(3079));
+        assert_eq!(ladder_table_size(1_610_612_742), None);
+    }
+
+    #[test]
+    fn diff_primes_test() {
+        let primes = crate::generator::prime_generator(1000u32, Vec::new(), 0);
+        assert_eq!(diff_primes(&primes, &primes), None);
+        assert_eq!(diff_primes::<u32>(&[], &[]), None);
+
+        let mut planted = primes.clone();
+        planted[100] = 548;
+        assert_eq!(
+            diff_primes(&primes, &planted),
+            Some((100, Some(547), Some(548)))
+        );
+
+        assert_eq!(
+            diff_primes(&primes[..10], &primes),
+            Some((10, None, Some(31)))
+        );
+        assert_eq!(diff_primes(&primes, &[]), Some((0, Some(2), None)));
+    }
+
+    #[test]
+    fn ignorant_cutoff_test() {
+        for n in 0u64..20_000 {
+            let expected = deterministic_is_prime(n);
+            for cutoff in [0, 1, 2, 3, 10, 1000, u64::MAX] {
+                assert_eq!(ignorant_is_prime_cutoff(n, &[], cutoff), expected, "{}", n);
+                assert_eq!(
+                    ignorant_is_prime_cutoff(n, &[2, 3, 5, 7], cutoff),
+                    expected,
+                    "{}",
+                    n
+                );
+            }
+        }
+        // 1000003 * 1000033, with no factor below the cutoff.
+        assert!(!ignorant_is_prime(1_000_036_000_099u64, &[]));
+        // Wider types still use Miller-Rabin for the numbers that fit in a u64.
+        assert!(ignorant_is_prime_cutoff(1_000_000_007u128, &[], 0));
+        assert!(!ignorant_is_prime_cutoff(1_000_036_000_099u128, &[], 0));
+        // Numbers that don't fit are divided up to the square root, ignoring the cutoff: 2^64 + 1 = 274177 * 67280421310721.
+        assert!(!ignorant_is_prime_cutoff((1u128 << 64) + 1, &[], 0));
+    }
+
+    #[test]
+    fn fast_divisors_test() {
+        let primes = crate::generator::prime_generator(70_000u64, Vec::new(), 0);
+        let divisors = FastDivisors::new(&primes);
+        assert_eq!(divisors.primes(), primes);
+        for n in 0..100_000 {
+            assert_eq!(is_prime_fast(n, &divisors), is_prime(n, &primes), "{}", n);
+        }
+        // The list reaches the square root of every one of them.
+        for n in [4_294_967_291, 4_294_967_297, 65_521 * 65_537, 4_899_999_997] {
+            assert_eq!(
+                is_prime_fast(n, &divisors),
+                deterministic_is_prime(n),
+                "{}",
+                n
+            );
+        }
+        assert!(is_prime_fast(2, &FastDivisors::new(&[])));
+
+        // The multiples of 2, 3 and 5 are rejected even if the list doesn't have them.
+        for known in [&[][..], &[2], &[2, 3], &[7, 11, 13], &primes[..10]] {
+            let divisors = FastDivisors::new(known);
+            for n in 0..10_000 {
+                assert_eq!(
+                    is_prime_fast(n, &divisors),
+                    is_prime(n, known),
+                    "{} {:?}",
+                    n,
+                    known
+                );
+            }
+        }
+        assert!(!is_prime_fast(25, &FastDivisors::new(&[])));
+    }
+
+    #[test]
+    fn largest_prime_in_type_test() {
+        assert_eq!(largest_prime_in_type::<u8>(), 251);
+        assert_eq!(largest_prime_in_type::<i8>(), 127);
+        assert_eq!(largest_prime_in_type::<u16>(), 65_521);
+        assert_eq!(largest_prime_in_type::<u32>(), 4_294_967_291);
+        assert_eq!(largest_prime_in_type::<u64>(), 18_446_744_073_709_551_557);
+        assert_eq!(largest_prime_in_type::<i128>(), i128::MAX); // 2^127 - 1
+
+        // The search gives the same results as the table.
+        assert_eq!(largest_prime_below(u8::MAX), 251);
+        assert_eq!(largest_prime_below(i16::MAX), 32_749);
+        assert_eq!(largest_prime_below(u32::MAX), 4_294_967_291);
+        assert_eq!(largest_prime_below(i64::MAX), 9_223_372_036_854_775_783);
+        assert_eq!(largest_prime_below(u64::MAX), 18_446_744_073_709_551_557);
+
+        // The 128-bit ones can't be checked by trial division.
+        for (p, max) in [
+            (LARGEST_PRIMES[8], u128::MAX),
+            (LARGEST_PRIMES[9], i128::MAX as u128),
+        ] {
+            assert!(crate::big::baillie_psw(&p.into()));
+            assert!((p + 1..=max).all(|n| !crate::big::baillie_psw(&n.into())));
+        }
+    }
+
+    #[test]
+    fn is_prime_result_test() {
+        assert_eq!(is_prime_result(0, &[]), Ok(false));
+        assert_eq!(is_prime_result(1, &[]), Ok(false));
+        assert_eq!(is_prime_result(2, &[]), Ok(true));
+        assert_eq!(is_prime_result(3, &[]), Ok(true));
+        // Multiples of 2, 3 and 5 don't need a list, like in is_prime.
+        assert_eq!(is_prime_result(4, &[]), Ok(false));
+        assert_eq!(is_prime_result(25, &[]), Ok(false));
+        assert_eq!(is_prime_result(4, &[2]), Ok(false));
+        assert_eq!(is_prime_result(47, &[]), Ok(true));
+        assert_eq!(
+            is_prime_result(49, &[]),
+            Err(InsufficientCache { needed_up_to: 7 })
+        );
+        assert_eq!(
+            is_prime_result(49, &[2, 3, 5]),
+            Err(InsufficientCache { needed_up_to: 7 })
+        );
+        assert_eq!(is_prime_result(49, &[2, 3, 5, 7]), Ok(false));
+        assert_eq!(
+            is_prime_result(53, &[2, 3, 5]),
+            Err(InsufficientCache { needed_up_to: 7 })
+        );
+        assert_eq!(is_prime_result(53, &[2, 3, 5, 7]), Ok(true));
+
+        // Extending the list and retrying always gives an answer.
+        let primes = crate::generator::prime_generator(1000u32, Vec::new(), 0);
+        for n in 0..20_000 {
+            let result = match is_prime_result(n, &primes[..5]) {
+                Err(e) => is_prime_result(
+                    n,
+                    &crate::generator::prime_generator(2 * e.needed_up_to + 1, Vec::new(), 0),
+                ),
+                ok => ok,
+            };
+            assert_eq!(result, Ok(is_prime(n, &primes)), "{}", n);
+        }
+    }
+
+    #[test]
+    fn witnesses_for_test() {
+        let mut previous = 0;
+        for (bound, len) in WITNESS_BOUNDS {
+            assert_eq!(witnesses_for(previous), &MILLER_RABIN_WITNESSES[..len]);
+            assert_eq!(witnesses_for(bound - 1), &MILLER_RABIN_WITNESSES[..len]);
+            assert!(witnesses_for(bound).len() > len);
+            // The bound is the pseudoprime that fools the smaller set.
+            assert!(!deterministic_is_prime(bound), "{}", bound);
+            assert!(!mont_is_prime(bound), "{}", bound);
+            previous = bound;
+        }
+        assert_eq!(witnesses_for(u64::MAX), &MILLER_RABIN_WITNESSES);
+
+        for n in (0..100_000).chain(3_215_031_000..3_215_032_000) {
+            let expected = n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0);
+            assert_eq!(deterministic_is_prime(n), expected, "{}", n);
+            assert_eq!(mont_is_prime(n), expected, "{}", n);
+        }
+    }
+
+    #[test]
+    fn is_prime_iter_test() {
+        let primes = crate::generator::prime_generator(1000u32, Vec::new(), 0);
+        for n in 0..1_000_000 {
+            assert_eq!(
+                is_prime_iter(n, primes.iter().cloned()),
+                is_prime(n, &primes),
+                "{}",
+                n
+            );
+        }
+        // The iterator isn't consumed after the square root.
+        let mut divisors = primes.iter().cloned();
+        assert!(is_prime_iter(101, divisors.by_ref()));
+        assert_eq!(divisors.next(), Some(13));
+        // Nor after a divisor.
+        let mut divisors = primes.iter().cloned();
+        assert!(!is_prime_iter(49, divisors.by_ref()));
+        assert_eq!(divisors.next(), Some(11));
+    }
+
+    #[test]
+    fn verify_primes_test() {
+        let primes = crate::generator::prime_generator(100_000u64, Vec::new(), 0);
+        assert_eq!(verify_primes(&primes, 100_000, usize::MAX), Ok(()));
+        assert_eq!(verify_primes(&primes, 100_000, 100), Ok(()));
+        assert_eq!(verify_primes(&[], 2, 100), Ok(()));
+        assert_eq!(
+            verify_primes(&[], 3u32, 100),
+            Err(VerificationError::Missing { value: 2 })
+        );
+
+        // Every kind of corruption is found when every index is checked.
+        let mut corrupted = primes.clone();
+        corrupted.insert(5001, primes[5000] + 1);
+        assert_eq!(
+            verify_primes(&corrupted, 100_000, usize::MAX),
+            Err(VerificationError::Composite {
+                index: 5001,
+                value: primes[5000] + 1
+            })
+        );
+        let mut corrupted = primes.clone();
+        corrupted.remove(5000);
+        assert_eq!(
+            verify_primes(&corrupted, 100_000, usize::MAX),
+            Err(VerificationError::Missing {
+                value: primes[5000]
+            })
+        );
+        let mut corrupted = primes.clone();
+        corrupted.insert(5001, primes[5000]);
+        assert_eq!(
+            verify_primes(&corrupted, 100_000, usize::MAX),
+            Err(VerificationError::Unordered {
+                index: 5001,
+                value: primes[5000]
+            })
+        );
+        let mut corrupted = primes.clone();
+        corrupted.pop();
+        assert!(verify_primes(&corrupted, 100_000, usize::MAX).is_err());
+
+        // A list where a tenth of the primes are composite is almost surely caught by 100 samples.
+        let mut corrupted = primes.clone();
+        corrupted
+            .iter_mut()
+            .step_by(10)
+            .skip(1)
+            .for_each(|p| *p += 1);
+        assert!(verify_primes(&corrupted, 100_000, 100).is_err());
+
+        assert_eq!(
+            VerificationError::Missing { value: 5 }.to_string(),
+            "5 is prime, but it's missing"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "prefetch")]
+    fn is_prime_prefetch_test() {
+        let primes = crate::generator::prime_generator(100_000u64, Vec::new(), 0);
+        for distance in [0, 1, PREFETCH_DISTANCE, 1000] {
+            for n in (0..10_000_000_000u64).step_by(999_983) {
+                assert_eq!(
+                    is_prime_prefetch(n, &primes, distance),
+                    is_prime(n, &primes)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn prime_pi_test() {
+        let primes = crate::generator::prime_generator(10_000u32, Vec::new(), 0);
+        for n in 0..=9974u32 {
+            let expected = primes.iter().filter(|&&p| p < n).count() as u32;
+            assert_eq!(prime_pi(n, &primes), Ok(expected), "{}", n);
+        }
+        assert_eq!(prime_pi(9974, &primes), Ok(1229));
+        assert_eq!(
+            prime_pi(9975, &primes),
+            Err(InsufficientCache { needed_up_to: 9974 })
+        );
+        assert_eq!(prime_pi(2u32, &[]), Ok(0));
+        assert_eq!(
+            prime_pi(3u32, &[]),
+            Err(InsufficientCache { needed_up_to: 2 })
+        );
+        assert_eq!(prime_pi(3u32, &[2]), Ok(1));
+    }
+
+    #[test]
+    fn is_probable_prime_test() {
+        let primes = crate::generator::prime_generator(10_000u64, Vec::new(), 0);
+        for n in 0..10_000u64 {
+            assert_eq!(
+                is_probable_prime(n as u128, 0),
+                primes.binary_search(&n).is_ok(),
+                "{}",
+                n
+            );
+        }
+
+        let two_64 = 1u128 << 64;
+        assert!(is_probable_prime(u64::MAX as u128 - 58, 0));
+        assert!(!is_probable_prime(two_64, 0));
+        assert!(!is_probable_prime(two_64 + 1, 0)); // 274177 * 67280421310721
+        assert!(is_probable_prime(two_64 + 13, 0));
+        assert!(is_probable_prime((1 << 61) - 1, 0));
+        assert!(is_probable_prime((1 << 89) - 1, 20));
+        assert!(is_probable_prime((1 << 107) - 1, 20));
+        assert!(is_probable_prime(u128::MAX >> 1, 20));
+        assert!(!is_probable_prime(((1 << 61) - 1) * ((1 << 31) - 1), 20));
+        assert!(!is_probable_prime(u128::MAX, 20));
+
+        // Squares, of which the ones of the Wieferich primes are pseudoprimes to base 2.
+        assert_eq!(mod_pow(2, 1093 * 1093 - 1, 1093 * 1093), 1);
+        assert!(!is_probable_prime(1093 * 1093, 0));
+        let p = two_64 + 13;
+        assert!(!is_probable_prime(p * 1_000_000_007, 0));
+        assert!(!is_probable_prime(((1 << 61) - 1) * ((1 << 61) - 1), 0));
+
+        // The fixed witnesses are fooled, the random ones aren't.
+        assert!(is_probable_prime(MILLER_RABIN_LIMIT, 0));
+        assert!(!is_probable_prime(MILLER_RABIN_LIMIT, 20));
+
+        assert_eq!(mul_mod_u128(u128::MAX - 1, u128::MAX - 1, u128::MAX), 1);
+    }
+
+    #[test]
+    fn checked_is_prime_test() {
+        let primes = crate::generator::prime_generator(200u32, Vec::new(), 0);
+        // Up to 7: the square root of 64 is already 8, and the list can't say that 8 isn't prime.
+        let short = &primes[..4];
+        for n in 0..40_000u32 {
+            let expected = deterministic_is_prime(n as u64);
+            assert_eq!(checked_is_prime(n, &primes), Some(expected), "{}", n);
+            let undecided = n >= 64 && short.iter().all(|p| n % p != 0);
+            let result = checked_is_prime(n, short);
+            assert_eq!(result, (!undecided).then_some(expected), "{}", n);
+        }
+        assert_eq!(checked_is_prime(63, short), Some(false));
+        assert_eq!(checked_is_prime(67, short), None);
+        assert_eq!(checked_is_prime(3u32, &[]), Some(true));
+        // Multiples of 2, 3 and 5 don't need the list.
+        assert_eq!(checked_is_prime(25u32, &[]), Some(false));
+        assert_eq!(checked_is_prime(49u32, &[]), None);
+    }
 }
@@ -0,0 +1,127 @@
+use crate::generator::*;
+use num::integer::Roots;
+use num::{CheckedAdd, FromPrimitive, ToPrimitive};
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when searching for a pattern that can't occur infinitely often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InadmissiblePattern {
+    /// The prime whose residues are all covered by the pattern.
+    pub modulus: u64,
+}
+
+impl fmt::Display for InadmissiblePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the pattern covers every residue modulo {}, so one of its elements is always divisible by it",
+            self.modulus
+        )
+    }
+}
+
+impl Error for InadmissiblePattern {}
+
+/// Returns the first prime whose residues are all covered by the pattern, if any.
+fn covering_prime(pattern: &[u64]) -> Option<u64> {
+    // A pattern with k elements can't cover all the residues of a prime greater than k.
+    let k = pattern.len() as u64;
+    (2..=k).filter(|&q| (2..q).all(|d| q % d != 0)).find(|&q| {
+        let mut covered = vec![false; q as usize];
+        pattern
+            .iter()
+            .for_each(|n| covered[(n % q) as usize] = true);
+        covered.into_iter().all(|c| c)
+    })
+}
+
+/// Checks if the offsets of a pattern can all be prime infinitely often.
+///
+/// A pattern is admissible if, for every prime p, there is at least one residue modulo p that isn't hit by any offset. Otherwise, one of the numbers is always a multiple of p.
+///
+/// # Examples
+///
+/// ```
+/// use prime::constellation::*;
+///
+/// assert!(is_admissible(&[0, 2, 6])); // Prime triplets like 5, 7, 11.
+/// assert!(!is_admissible(&[0, 2, 4])); // One of them is always divisible by 3.
+/// ```
+pub fn is_admissible(pattern: &[u64]) -> bool {
+    covering_prime(pattern).is_none()
+}
+
+/// Finds every prime p less than until such that p plus each offset of the pattern is prime, and returns them in a Vec.
+///
+/// The pattern is checked with [`is_admissible`] first, and an error is returned if it can't occur infinitely often.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::constellation::*;
+///
+/// assert_eq!(prime_constellations(20, &[0, 2], Vec::new()), Ok(vec![3, 5, 11, 17]));
+/// assert_eq!(prime_constellations(20, &[0, 2, 6], Vec::new()), Ok(vec![5, 11, 17]));
+/// assert!(prime_constellations(20, &[0, 2, 4], Vec::new()).is_err());
+/// ```
+pub fn prime_constellations<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    pattern: &[N],
+    known_primes: Vec<N>,
+) -> Result<Vec<N>, InadmissiblePattern> {
+    let offsets: Vec<u64> = pattern.iter().map(|n| n.to_u64().unwrap()).collect();
+    if let Some(modulus) = covering_prime(&offsets) {
+        return Err(InadmissiblePattern { modulus });
+    }
+
+    let widest = pattern.iter().max().cloned().unwrap_or(N::zero());
+    let primes = prime_generator(until + widest, known_primes, N::zero());
+
+    Ok(primes
+        .iter()
+        .take_while(|&&p| p < until)
+        .filter(|&&p| {
+            pattern
+                .iter()
+                .all(|&offset| primes.binary_search(&(p + offset)).is_ok())
+        })
+        .cloned()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admissible_test() {
+        assert!(is_admissible(&[]));
+        assert!(is_admissible(&[0]));
+        assert!(is_admissible(&[0, 2]));
+        assert!(is_admissible(&[0, 2, 6]));
+        assert!(is_admissible(&[0, 4, 6]));
+        assert!(is_admissible(&[0, 2, 6, 8]));
+        assert!(!is_admissible(&[0, 1]));
+        assert!(!is_admissible(&[0, 2, 4]));
+        assert!(!is_admissible(&[0, 2, 6, 8, 10]));
+    }
+
+    #[test]
+    fn constellations_test() {
+        assert_eq!(
+            prime_constellations(100, &[0, 2], Vec::new()),
+            Ok(vec![3, 5, 11, 17, 29, 41, 59, 71])
+        );
+        assert_eq!(
+            prime_constellations(100, &[0, 2, 6, 8], Vec::new()),
+            Ok(vec![5, 11])
+        );
+        assert_eq!(
+            prime_constellations(100, &[0, 2, 4], Vec::new()),
+            Err(InadmissiblePattern { modulus: 3 })
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt::Display;
+
+/// Formats the primes in a table with the columns specified, like the output of the classic `primes` program.
+///
+/// Every number is right-aligned to the width of the longest one, the columns are separated by a space and every row ends with a newline. The last row has fewer numbers if the length of the list isn't a multiple of columns.
+///
+/// # Examples
+///
+/// ```
+/// use prime::display::*;
+///
+/// let table = format_table(&[2, 3, 5, 7, 11, 13, 17], 3);
+/// assert_eq!(table, " 2  3  5\n 7 11 13\n17\n");
+/// ```
+///
+/// # Panics
+///
+/// The function panics if columns is 0.
+pub fn format_table<N: Display>(primes: &[N], columns: usize) -> String {
+    assert!(columns != 0, "the table needs at least a column");
+    let cells: Vec<String> = primes.iter().map(|p| p.to_string()).collect();
+    let width = cells.iter().map(String::len).max().unwrap_or(0);
+
+    let mut table = String::with_capacity(cells.len() * (width + 1));
+    for row in cells.chunks(columns) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|cell| format!("{:>width$}", cell, width = width))
+            .collect();
+        table.push_str(&row.join(" "));
+        table.push('\n');
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::*;
+
+    #[test]
+    fn format_table_test() {
+        let primes = prime_generator(100u32, Vec::new(), 0);
+        let table = format_table(&primes, 4);
+        let rows: Vec<&str> = table.lines().collect();
+        // 25 primes, the last one alone.
+        assert_eq!(rows.len(), 7);
+        assert!(rows[..6].iter().all(|row| row.len() == 4 * 2 + 3));
+        assert_eq!(rows[0], " 2  3  5  7");
+        assert_eq!(rows[6], "97");
+        assert_eq!(
+            table.split_whitespace().collect::<Vec<_>>(),
+            primes.iter().map(|p| p.to_string()).collect::<Vec<_>>()
+        );
+
+        assert_eq!(format_table(&[2, 3], 4), "2 3\n");
+        assert_eq!(format_table(&[101, 103, 107], 1), "101\n103\n107\n");
+        assert_eq!(format_table::<u32>(&[], 4), "");
+    }
+
+    #[test]
+    #[should_panic]
+    fn format_table_panic() {
+        format_table(&[2, 3], 0);
+    }
+}
@@ -0,0 +1,790 @@
+use crate::calculator::*;
+use crate::generator::*;
+use num::integer::Roots;
+use num::range_step;
+use num::{CheckedAdd, FromPrimitive, Integer, ToPrimitive};
+use rayon::prelude::*;
+use std::borrow::Cow;
+
+/// Calculates the prime factorization of n! with Legendre's formula, without calculating n!.
+///
+/// The exponent of every prime p is the sum of n / p^k for every k. The known primes are used if they reach n, otherwise they are calculated up to n included.
+///
+/// Returns `None` if the primes have to be calculated and n doesn't fit in N.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// // 10! = 2^8 * 3^4 * 5^2 * 7
+/// assert_eq!(factorial_factorization(10, &[2u32, 3, 5, 7, 11]), Some(vec![(2, 8), (3, 4), (5, 2), (7, 1)]));
+///
+/// // The primes up to 300 don't fit in u8.
+/// assert_eq!(factorial_factorization::<u8>(300, &[]), None);
+/// ```
+pub fn factorial_factorization<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: u64,
+    known_primes: &[N],
+) -> Option<Vec<(N, u64)>> {
+    if n < 2 {
+        return Some(Vec::new());
+    }
+
+    let reaches_n = known_primes
+        .last()
+        .is_some_and(|p| p.to_u64().unwrap() >= n);
+    let generated;
+    let primes = if reaches_n {
+        known_primes
+    } else {
+        generated = prime_generator_inclusive(N::from_u64(n)?, known_primes.to_vec(), N::zero());
+        &generated
+    };
+
+    let factorization = primes
+        .iter()
+        .map(|&p| (p, p.to_u64().unwrap()))
+        .take_while(|&(_, q)| q <= n)
+        .map(|(p, q)| {
+            let mut exponent = 0;
+            let mut power = q;
+            loop {
+                exponent += n / power;
+                match power.checked_mul(q) {
+                    Some(next) if next <= n => power = next,
+                    _ => break,
+                }
+            }
+            (p, exponent)
+        })
+        .collect();
+    Some(factorization)
+}
+
+/// Calculates how many zeros n! ends with, which is the exponent of 5 in its prime factorization.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(factorial_trailing_zeros(10), 2); // 3628800
+/// assert_eq!(factorial_trailing_zeros(100), 24);
+/// ```
+pub fn factorial_trailing_zeros(n: u64) -> u64 {
+    // There are always more 2s than 5s, so every 5 makes a 10.
+    let mut zeros = 0;
+    let mut power = 5u64;
+    while power <= n {
+        zeros += n / power;
+        power = match power.checked_mul(5) {
+            Some(p) => p,
+            None => break,
+        };
+    }
+    zeros
+}
+
+/// How many numbers every task of [`par_aliquot_sums`] handles.
+const ALIQUOT_CHUNK_LEN: usize = 1 << 16;
+
+/// Parallelely calculates the aliquot sum (the sum of the proper divisors) of every number less than until, and returns them in a Vec where the element at index n is the sum of n.
+///
+/// The numbers are factorized with a segmented sieve: every chunk divides its numbers by the primes up to the square root of until, and then the sum of the divisors is the product of the sums of the powers of every prime factor. The sums of 0 and 1 are 0.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// let sums = par_aliquot_sums(300u32);
+/// assert_eq!(sums[6], 6); // A perfect number.
+/// assert_eq!((sums[220], sums[284]), (284, 220)); // An amicable pair.
+/// ```
+///
+/// # Panics
+///
+/// The function panics if a sum doesn't fit in N.
+pub fn par_aliquot_sums<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send>(
+    until: N,
+) -> Vec<N> {
+    let until = until.to_usize().unwrap();
+    let base_primes = prime_generator(until.sqrt() as u64 + 1, Vec::new(), 0);
+
+    let mut sums = vec![N::zero(); until];
+    sums.par_chunks_mut(ALIQUOT_CHUNK_LEN)
+        .enumerate()
+        .for_each(|(c, chunk)| {
+            let start = (c * ALIQUOT_CHUNK_LEN) as u64;
+            let end = start + chunk.len() as u64;
+            // The part of every number that hasn't been factorized yet.
+            let mut rest: Vec<u64> = (start..end).collect();
+            let mut divisor_sums = vec![1u64; chunk.len()];
+
+            for &p in &base_primes {
+                for m in range_step(start.next_multiple_of(p).max(p), end, p) {
+                    let i = (m - start) as usize;
+                    let mut power = 1;
+                    let mut power_sum = 1;
+                    while rest[i].is_multiple_of(p) {
+                        rest[i] /= p;
+                        power *= p;
+                        power_sum += power;
+                    }
+                    divisor_sums[i] *= power_sum;
+                }
+            }
+
+            for (i, sum) in chunk.iter_mut().enumerate() {
+                let n = start + i as u64;
+                if n < 2 {
+                    continue;
+                }
+                // What's left is a prime greater than the square root.
+                if rest[i] > 1 {
+                    divisor_sums[i] *= rest[i] + 1;
+                }
+                *sum = N::from_u64(divisor_sums[i] - n).unwrap();
+            }
+        });
+    sums
+}
+
+/// Returns every amicable pair (a, b) with a < b < until: the aliquot sum of a is b, and the aliquot sum of b is a.
+///
+/// The sums are calculated with [`par_aliquot_sums`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(amicable_pairs(2000u32), vec![(220, 284), (1184, 1210)]);
+/// ```
+pub fn amicable_pairs<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send>(
+    until: N,
+) -> Vec<(N, N)> {
+    let sums = par_aliquot_sums(until);
+    sums.iter()
+        .enumerate()
+        .filter_map(|(a, &b)| {
+            let b_index = b.to_usize().unwrap();
+            (a < b_index && b_index < sums.len() && sums[b_index].to_usize() == Some(a))
+                .then(|| (N::from_usize(a).unwrap(), b))
+        })
+        .collect()
+}
+
+/// Returns every sociable chain of at most max_len numbers less than until: a cycle of numbers where each one is the aliquot sum of the one before it.
+///
+/// Chains of 1 number are the perfect numbers, and chains of 2 are the amicable pairs. Every chain starts from its smallest number, and the chains are ordered by it.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(sociable_chains(20_000u32, 5), vec![
+///     vec![6],
+///     vec![28],
+///     vec![220, 284],
+///     vec![496],
+///     vec![1184, 1210],
+///     vec![2620, 2924],
+///     vec![5020, 5564],
+///     vec![6232, 6368],
+///     vec![8128],
+///     vec![10744, 10856],
+///     vec![12285, 14595],
+///     vec![12496, 14288, 15472, 14536, 14264],
+///     vec![17296, 18416],
+/// ]);
+/// ```
+pub fn sociable_chains<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send>(
+    until: N,
+    max_len: usize,
+) -> Vec<Vec<N>> {
+    let sums: Vec<usize> = par_aliquot_sums(until)
+        .into_iter()
+        .map(|s| s.to_usize().unwrap())
+        .collect();
+    (2..sums.len())
+        .filter_map(|start| {
+            let mut chain = vec![start];
+            let mut n = sums[start];
+            // Only the smallest number of the chain reports it.
+            while n != start && n > start && n < sums.len() && chain.len() < max_len {
+                chain.push(n);
+                n = sums[n];
+            }
+            (n == start).then(|| {
+                chain
+                    .into_iter()
+                    .map(|n| N::from_usize(n).unwrap())
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+/// Counts the prime factors of n with multiplicity, Ω(n), by trial division. Ω(0) and Ω(1) are 0.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(big_omega(12), 3); // 2 * 2 * 3
+/// assert_eq!(big_omega(13), 1);
+/// ```
+pub fn big_omega(mut n: u64) -> u32 {
+    if n < 2 {
+        return 0;
+    }
+    let mut count = n.trailing_zeros();
+    n >>= count;
+    let mut d = 3;
+    while d <= n / d {
+        while n.is_multiple_of(d) {
+            n /= d;
+            count += 1;
+        }
+        d += 2;
+    }
+    if n > 1 {
+        count += 1;
+    }
+    count
+}
+
+/// Calculates the Liouville function λ(n) = (-1)^Ω(n) for every n less than until, and returns them in a Vec where the element at index n is λ(n).
+///
+/// Ω is found with a linear sieve, which reaches every composite number exactly once, from its smallest prime factor. λ(0) isn't defined, and it's 0 in the Vec.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(liouville_sieve(7), vec![0, 1, -1, -1, 1, -1, 1]);
+/// ```
+pub fn liouville_sieve(until: usize) -> Vec<i8> {
+    let mut omegas = vec![0u8; until];
+    let mut primes = Vec::new();
+    for i in 2..until {
+        if omegas[i] == 0 {
+            omegas[i] = 1;
+            primes.push(i);
+        }
+        for &p in &primes {
+            match p.checked_mul(i) {
+                Some(m) if m < until => omegas[m] = omegas[i] + 1,
+                _ => break,
+            }
+            if i.is_multiple_of(p) {
+                break;
+            }
+        }
+    }
+    omegas
+        .iter()
+        .enumerate()
+        .map(|(n, &omega)| match n {
+            0 => 0,
+            _ if omega % 2 == 0 => 1,
+            _ => -1,
+        })
+        .collect()
+}
+
+/// Calculates L(n), the sum of λ(k) for every k from 1 to n, with [`liouville_sieve`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(liouville_summatory(1), 1);
+/// assert_eq!(liouville_summatory(7), -1);
+/// ```
+pub fn liouville_summatory(n: usize) -> i64 {
+    liouville_sieve(n + 1).iter().map(|&l| l as i64).sum()
+}
+
+/// Factorizes n into its prime factors and their exponents, in increasing order, or returns `None` if n isn't positive.
+///
+/// n is divided by every known prime up to the square root of what's left of it. If the list doesn't reach it, the list is copied and extended with [`extend_primes`], so it can be short or even empty. What's left at the end is 1 or a prime. It's required for the list to be ordered and to contain every prime up to its last element.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(factorize(360u32, &[2, 3, 5]), Some(vec![(2, 3), (3, 2), (5, 1)]));
+/// assert_eq!(factorize(97u32, &[]), Some(vec![(97, 1)]));
+/// assert_eq!(factorize(1u32, &[]), Some(vec![]));
+/// assert_eq!(factorize(0u32, &[]), None);
+/// ```
+pub fn factorize<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: N,
+    known_primes: &[N],
+) -> Option<Vec<(N, u32)>> {
+    if n <= N::zero() {
+        return None;
+    }
+    let mut primes = Cow::Borrowed(known_primes);
+    let mut rest = n;
+    let mut factors = Vec::new();
+    let mut i = 0;
+    loop {
+        if i == primes.len() {
+            let added = extend_primes(primes.to_mut(), rest.sqrt() + N::one()).len();
+            if added == 0 {
+                break;
+            }
+        }
+        let p = primes[i];
+        if p > rest / p {
+            break;
+        }
+        let mut exponent = 0;
+        while rest.is_multiple_of(&p) {
+            rest = rest / p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+        i += 1;
+    }
+    if !rest.is_one() {
+        factors.push((rest, 1));
+    }
+    Some(factors)
+}
+
+/// Calculates Euler's totient function φ(n), how many numbers from 1 to n are coprime with n, with the product of (1 - 1/p) over the distinct primes p that divide n.
+///
+/// The prime factors are found with [`factorize`], and n is divided by every p before being multiplied by p - 1, so that the calculation is exact and can't overflow.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(totient(36u32, &[2, 3]), 12);
+/// assert_eq!(totient(97u32, &[]), 96);
+/// assert_eq!(totient(1u32, &[]), 1);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if n isn't positive.
+pub fn totient<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: N,
+    known_primes: &[N],
+) -> N {
+    factorize(n, known_primes)
+        .expect("the totient is only defined for positive numbers")
+        .into_iter()
+        .fold(n, |phi, (p, _)| phi / p * (p - N::one()))
+}
+
+/// Factorizes n into its prime factors and their exponents, in increasing order, dividing it by the known primes and then by every odd number after the last one.
+///
+/// Unlike [`factorize`], the list isn't extended, so only a constant amount of memory is used even when n has a factor near 2^32.
+fn factorize_u64(mut n: u64, known_primes: &[u64]) -> Vec<(u64, u32)> {
+    let mut factors = Vec::new();
+    let mut divide = |n: &mut u64, p: u64| {
+        let mut exponent = 0;
+        while n.is_multiple_of(p) {
+            *n /= p;
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((p, exponent));
+        }
+    };
+
+    for &p in known_primes {
+        if p > n / p {
+            break;
+        }
+        divide(&mut n, p);
+    }
+    let mut d = match known_primes.last() {
+        Some(&p) if p >= 3 => p + 2,
+        _ => {
+            if known_primes.is_empty() {
+                divide(&mut n, 2);
+            }
+            3
+        }
+    };
+    while d <= n / d {
+        divide(&mut n, d);
+        d += 2;
+    }
+    if n > 1 {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+/// Returns the multiplicative order of a modulo n: the smallest k greater than 0 such that a^k is 1 modulo n, or `None` if a and n aren't coprime.
+///
+/// The order divides φ(n), so φ(n) is divided by each of its prime factors while a^k stays 1. The known primes are used to factorize n and φ(n), and it's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(multiplicative_order(10, 7, &[2, 3]), Some(6));
+/// assert_eq!(multiplicative_order(2, 7, &[2, 3]), Some(3));
+/// assert_eq!(multiplicative_order(2, 6, &[2, 3]), None);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if n is 0.
+pub fn multiplicative_order(a: u64, n: u64, known_primes: &[u64]) -> Option<u64> {
+    assert!(n != 0, "there is no multiplicative order modulo 0");
+    if a.gcd(&n) != 1 {
+        return None;
+    }
+    let phi: u64 = factorize_u64(n, known_primes)
+        .iter()
+        .map(|&(p, e)| p.pow(e - 1) * (p - 1))
+        .product();
+    let mut order = phi;
+    for (q, _) in factorize_u64(phi, known_primes) {
+        while order.is_multiple_of(q) && mod_pow(a, order / q, n) == 1 {
+            order /= q;
+        }
+    }
+    Some(order)
+}
+
+/// Returns the length of the period of the decimal expansion of 1 / denominator, or 0 if the expansion is finite.
+///
+/// The 2s and 5s of the denominator only delay the start of the period, so the length is the [`multiplicative_order`] of 10 modulo the rest of it. The known primes are used to factorize it, and it's required for the list to be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::factorization::*;
+///
+/// assert_eq!(decimal_period_length(7, &[2, 3, 5]), 6); // 0.(142857)
+/// assert_eq!(decimal_period_length(3, &[2, 3, 5]), 1); // 0.(3)
+/// assert_eq!(decimal_period_length(12, &[2, 3, 5]), 1); // 0.08(3)
+/// assert_eq!(decimal_period_length(8, &[2, 3, 5]), 0); // 0.125
+/// ```
+///
+/// # Panics
+///
+/// The function panics if denominator is 0.
+pub fn decimal_period_length(denominator: u64, known_primes: &[u64]) -> u64 {
+    assert!(denominator != 0, "1 / 0 has no decimal expansion");
+    let mut rest = denominator >> denominator.trailing_zeros();
+    while rest.is_multiple_of(5) {
+        rest /= 5;
+    }
+    if rest == 1 {
+        return 0;
+    }
+    multiplicative_order(10, rest, known_primes).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factorial_factorization_test() {
+        assert_eq!(factorial_factorization::<u32>(0, &[]), Some(vec![]));
+        assert_eq!(factorial_factorization::<u32>(1, &[]), Some(vec![]));
+        assert_eq!(factorial_factorization::<u32>(2, &[]), Some(vec![(2, 1)]));
+        assert_eq!(
+            factorial_factorization::<u32>(10, &[]),
+            Some(vec![(2, 8), (3, 4), (5, 2), (7, 1)])
+        );
+
+        // n fits in u8 even if n + 1 doesn't, but only if the primes have to be calculated.
+        let factorization = factorial_factorization::<u8>(255, &[]).unwrap();
+        assert_eq!(factorization.last(), Some(&(251, 1)));
+        assert_eq!(factorial_factorization::<u8>(256, &[]), None);
+        assert_eq!(factorial_factorization::<u8>(u64::MAX, &[]), None);
+
+        let primes = prime_generator(200u64, Vec::new(), 0);
+        let factorization = factorial_factorization(100, &primes).unwrap();
+        assert_eq!(factorization.len(), 25);
+        assert_eq!(factorization[0], (2, 97));
+        assert_eq!(factorization.iter().find(|(p, _)| *p == 5), Some(&(5, 24)));
+        assert_eq!(factorization.last(), Some(&(97, 1)));
+
+        // The product matches the factorial.
+        let factorial: u64 = (1..=20).product();
+        let product: u64 = factorial_factorization::<u64>(20, &[2, 3])
+            .unwrap()
+            .iter()
+            .map(|&(p, e)| p.pow(e as u32))
+            .product();
+        assert_eq!(product, factorial);
+    }
+
+    #[test]
+    fn trailing_zeros_test() {
+        assert_eq!(factorial_trailing_zeros(0), 0);
+        assert_eq!(factorial_trailing_zeros(4), 0);
+        assert_eq!(factorial_trailing_zeros(5), 1);
+        assert_eq!(factorial_trailing_zeros(24), 4);
+        assert_eq!(factorial_trailing_zeros(25), 6);
+        assert_eq!(factorial_trailing_zeros(100), 24);
+        assert_eq!(
+            factorial_trailing_zeros(u64::MAX),
+            4_611_686_018_427_387_890
+        );
+
+        let primes = prime_generator(1000u64, Vec::new(), 0);
+        for n in [10, 125, 999] {
+            let five = factorial_factorization(n, &primes)
+                .unwrap()
+                .into_iter()
+                .find(|&(p, _)| p == 5)
+                .map_or(0, |(_, e)| e);
+            assert_eq!(factorial_trailing_zeros(n), five);
+        }
+    }
+
+    #[test]
+    fn aliquot_sums_test() {
+        assert_eq!(par_aliquot_sums(0u32), Vec::<u32>::new());
+        assert_eq!(par_aliquot_sums(3u32), vec![0, 0, 1]);
+
+        // Bigger than a chunk, so that the chunks after the first are checked too.
+        let sums = par_aliquot_sums(100_000u64);
+        for n in (2..2000).chain(99_000..100_000) {
+            let expected: u64 = (1..n).filter(|d| n % d == 0).sum();
+            assert_eq!(sums[n as usize], expected, "{}", n);
+        }
+        assert_eq!((sums[220], sums[284]), (284, 220));
+        assert_eq!(
+            (sums[6], sums[28], sums[496], sums[8128]),
+            (6, 28, 496, 8128)
+        );
+        assert_eq!(sums[99_991], 1);
+    }
+
+    #[test]
+    fn amicable_test() {
+        assert_eq!(amicable_pairs(2000u32), vec![(220, 284), (1184, 1210)]);
+        // 284 isn't less than until.
+        assert_eq!(amicable_pairs(284u32), vec![]);
+        assert_eq!(amicable_pairs(285u32), vec![(220, 284)]);
+
+        assert_eq!(
+            sociable_chains(10_000u32, 1),
+            vec![vec![6], vec![28], vec![496], vec![8128]]
+        );
+        assert_eq!(
+            sociable_chains(20_000u64, 4)
+                .into_iter()
+                .filter(|c| c.len() == 2)
+                .map(|c| (c[0], c[1]))
+                .collect::<Vec<_>>(),
+            amicable_pairs(20_000u64)
+        );
+        // The chain of 5 needs every element to be less than until.
+        assert!(sociable_chains(15_472u32, 5).iter().all(|c| c.len() < 5));
+    }
+
+    #[test]
+    fn liouville_test() {
+        assert_eq!(liouville_sieve(0), Vec::<i8>::new());
+        let lambdas = liouville_sieve(1000);
+        assert_eq!(lambdas[..5], [0, 1, -1, -1, 1]);
+        for (n, &lambda) in lambdas.iter().enumerate().skip(1) {
+            let expected = if big_omega(n as u64).is_multiple_of(2) {
+                1
+            } else {
+                -1
+            };
+            assert_eq!(lambda, expected, "{}", n);
+        }
+
+        assert_eq!(liouville_summatory(0), 0);
+        assert_eq!(liouville_summatory(2), 0);
+        // Pólya's conjecture holds for every n below 906150257.
+        assert!((2..1000).all(|n| liouville_summatory(n) <= 0));
+        assert_eq!(big_omega(u64::MAX), 7); // 3 * 5 * 17 * 257 * 641 * 65537 * 6700417
+        assert_eq!(big_omega(1 << 40), 40);
+    }
+
+    #[test]
+    #[ignore = "tries 2^31 divisors, run it with --release --ignored"]
+    fn big_omega_max_prime_test() {
+        // The largest prime that fits in u64: the divisors reach 2^32, whose square overflows.
+        assert_eq!(big_omega(18_446_744_073_709_551_557), 1);
+    }
+
+    #[test]
+    #[ignore = "tries 2^31 divisors, run it with --release --ignored"]
+    fn factorize_u64_max_prime_test() {
+        // The divisors reach 2^32, whose square overflows.
+        let p = 18_446_744_073_709_551_557;
+        assert_eq!(factorize_u64(p, &[]), vec![(p, 1)]);
+        let semiprime = 4_294_967_291 * 4_294_967_279;
+        assert_eq!(
+            factorize_u64(semiprime, &[2, 3]),
+            vec![(4_294_967_279, 1), (4_294_967_291, 1)]
+        );
+    }
+
+    #[test]
+    fn factorize_u64_test() {
+        let primes = prime_generator(100u64, Vec::new(), 0);
+        for known_primes in [&[][..], &[2], &[2, 3], &primes] {
+            assert_eq!(factorize_u64(1, known_primes), vec![]);
+            assert_eq!(factorize_u64(2, known_primes), vec![(2, 1)]);
+            assert_eq!(
+                factorize_u64(360, known_primes),
+                vec![(2, 3), (3, 2), (5, 1)]
+            );
+            assert_eq!(
+                factorize_u64(u64::MAX, known_primes),
+                vec![
+                    (3, 1),
+                    (5, 1),
+                    (17, 1),
+                    (257, 1),
+                    (641, 1),
+                    (65537, 1),
+                    (6700417, 1)
+                ]
+            );
+            assert_eq!(
+                factorize_u64(1_000_036_000_099, known_primes),
+                vec![(1_000_003, 1), (1_000_033, 1)]
+            );
+        }
+    }
+
+    #[test]
+    fn decimal_period_test() {
+        let primes = prime_generator(1000u64, Vec::new(), 0);
+        assert_eq!(decimal_period_length(1, &primes), 0);
+        assert_eq!(decimal_period_length(7, &primes), 6);
+        assert_eq!(decimal_period_length(3, &primes), 1);
+        assert_eq!(decimal_period_length(11, &primes), 2);
+        assert_eq!(decimal_period_length(13, &primes), 6);
+        assert_eq!(decimal_period_length(17, &primes), 16);
+        assert_eq!(decimal_period_length(81, &primes), 9);
+        assert_eq!(decimal_period_length(1000, &primes), 0);
+
+        // Compare to the long division, where the period ends when a remainder repeats.
+        for d in 1..2000u64 {
+            let mut seen = std::collections::HashMap::new();
+            let mut r = 1 % d;
+            let mut i = 0;
+            while r != 0 && !seen.contains_key(&r) {
+                seen.insert(r, i);
+                r = r * 10 % d;
+                i += 1;
+            }
+            let expected = if r == 0 { 0 } else { i - seen[&r] };
+            assert_eq!(decimal_period_length(d, &primes), expected, "{}", d);
+            assert_eq!(decimal_period_length(d, &[]), expected, "{}", d);
+        }
+    }
+
+    #[test]
+    fn multiplicative_order_test() {
+        let primes = prime_generator(1000u64, Vec::new(), 0);
+        assert_eq!(multiplicative_order(10, 7, &primes), Some(6));
+        assert_eq!(multiplicative_order(2, 7, &primes), Some(3));
+        assert_eq!(multiplicative_order(1, 7, &primes), Some(1));
+        assert_eq!(multiplicative_order(5, 1, &primes), Some(1));
+        assert_eq!(multiplicative_order(0, 7, &primes), None);
+        assert_eq!(multiplicative_order(14, 7, &primes), None);
+        assert_eq!(multiplicative_order(3, 1 << 20, &primes), Some(1 << 18));
+
+        // Compare to the powers computed one by one.
+        for n in 1..300u64 {
+            for a in 0..n {
+                let expected = (a.gcd(&n) == 1).then(|| {
+                    let mut power = a % n;
+                    let mut k = 1;
+                    while power != 1 % n {
+                        power = power * a % n;
+                        k += 1;
+                    }
+                    k
+                });
+                assert_eq!(multiplicative_order(a, n, &primes), expected, "{} {}", a, n);
+            }
+        }
+    }
+
+    #[test]
+    fn factorize_test() {
+        let primes = prime_generator(100u64, Vec::new(), 0);
+        for known_primes in [&[][..], &[2], &[2, 3], &primes] {
+            assert_eq!(factorize(0, known_primes), None);
+            assert_eq!(factorize(1, known_primes), Some(vec![]));
+            assert_eq!(factorize(2, known_primes), Some(vec![(2, 1)]));
+            assert_eq!(
+                factorize(360, known_primes),
+                Some(vec![(2, 3), (3, 2), (5, 1)])
+            );
+            assert_eq!(
+                factorize(1_000_003, known_primes),
+                Some(vec![(1_000_003, 1)])
+            );
+        }
+        // A semiprime of two big primes, for which the list is extended to a million.
+        assert_eq!(
+            factorize(1_000_036_000_099, &primes),
+            Some(vec![(1_000_003, 1), (1_000_033, 1)])
+        );
+        for n in 1..5000u64 {
+            assert_eq!(factorize(n, &[]).unwrap(), factorize_u64(n, &[]), "{}", n);
+            assert_eq!(
+                factorize(n, &primes).unwrap(),
+                factorize_u64(n, &primes),
+                "{}",
+                n
+            );
+        }
+        assert_eq!(factorize(-12i32, &[]), None);
+        assert_eq!(
+            factorize(u32::MAX, &[]),
+            Some(vec![(3, 1), (5, 1), (17, 1), (257, 1), (65537, 1)])
+        );
+        assert_eq!(factorize(251u8, &[]), Some(vec![(251, 1)]));
+        assert_eq!(factorize(u8::MAX, &[]), Some(vec![(3, 1), (5, 1), (17, 1)]));
+    }
+
+    #[test]
+    fn totient_test() {
+        let primes = prime_generator(1000u64, Vec::new(), 0);
+        assert_eq!(totient(36, &primes), 12);
+        assert_eq!(totient(1, &primes), 1);
+        for &p in &primes {
+            assert_eq!(totient(p, &[]), p - 1);
+        }
+        for n in 1..2000u64 {
+            let coprime = (1..=n).filter(|k| k.gcd(&n) == 1).count() as u64;
+            assert_eq!(totient(n, &primes), coprime, "{}", n);
+        }
+        // 2^32 - 1 = 3 * 5 * 17 * 257 * 65537
+        assert_eq!(totient(u32::MAX, &[]), 2 * 4 * 16 * 256 * 65536);
+    }
+
+    #[test]
+    #[should_panic]
+    fn totient_zero() {
+        totient(0u32, &[]);
+    }
+}
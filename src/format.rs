@@ -0,0 +1,304 @@
+use crate::calculator::*;
+use num::integer::Roots;
+use std::fmt::Display;
+use std::io::{self, Read, Write};
+
+/// The first bytes of every prime file.
+pub const MAGIC: [u8; 4] = *b"PRIM";
+
+/// The version of the format written by this library.
+pub const VERSION: u8 = 1;
+
+const LITTLE_ENDIAN: u8 = 0;
+const BIG_ENDIAN: u8 = 1;
+
+const NATIVE_ENDIAN: u8 = if cfg!(target_endian = "big") {
+    BIG_ENDIAN
+} else {
+    LITTLE_ENDIAN
+};
+
+/// The length of the header in bytes.
+pub const HEADER_LEN: usize = 8;
+
+/// A number that can be stored in a prime file.
+pub trait Element: Copy {
+    /// The size of the number in bytes.
+    const WIDTH: usize;
+
+    /// Writes the number in native endianness to the start of the buffer.
+    fn write_ne(self, buf: &mut [u8]);
+
+    /// Reads a number in native endianness from the start of the buffer.
+    fn read_ne(buf: &[u8]) -> Self;
+
+    /// Reverses the byte order of the number.
+    fn swap_bytes(self) -> Self;
+}
+
+macro_rules! impl_element {
+    ($($t:ty),*) => {
+        $(
+            impl Element for $t {
+                const WIDTH: usize = std::mem::size_of::<$t>();
+
+                fn write_ne(self, buf: &mut [u8]) {
+                    buf[..Self::WIDTH].copy_from_slice(&self.to_ne_bytes());
+                }
+
+                fn read_ne(buf: &[u8]) -> Self {
+                    <$t>::from_ne_bytes(buf[..Self::WIDTH].try_into().unwrap())
+                }
+
+                fn swap_bytes(self) -> Self {
+                    <$t>::swap_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_element!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// Writes the header of a prime file: the magic number, the version, the width of the elements and the endianness they're written in.
+pub fn write_header<N: Element, W: Write>(w: &mut W) -> io::Result<()> {
+    let mut header = [0; HEADER_LEN];
+    header[..4].copy_from_slice(&MAGIC);
+    header[4] = VERSION;
+    header[5] = N::WIDTH as u8;
+    header[6] = NATIVE_ENDIAN;
+    w.write_all(&header)
+}
+
+/// Reads the header of a prime file, returning whether the elements need their bytes swapped.
+///
+/// Returns an error of kind `InvalidData` if the magic number, version or width of the elements don't match.
+pub fn read_header<N: Element, R: Read>(r: &mut R) -> io::Result<bool> {
+    let mut header = [0; HEADER_LEN];
+    r.read_exact(&mut header)?;
+    if header[..4] != MAGIC {
+        return Err(invalid_data("not a prime file"));
+    }
+    if header[4] != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported version {} (expected {})",
+            header[4], VERSION
+        )));
+    }
+    if header[5] as usize != N::WIDTH {
+        return Err(invalid_data(format!(
+            "the file contains {}-byte numbers, but {}-byte numbers were requested",
+            header[5],
+            N::WIDTH
+        )));
+    }
+    match header[6] {
+        LITTLE_ENDIAN | BIG_ENDIAN => Ok(header[6] != NATIVE_ENDIAN),
+        e => Err(invalid_data(format!("unknown endianness flag {}", e))),
+    }
+}
+
+/// Writes the numbers one after the other, in native endianness.
+pub fn write_elements<N: Element, W: Write>(w: &mut W, elements: &[N]) -> io::Result<()> {
+    let mut buf = vec![0; elements.len() * N::WIDTH];
+    buf.chunks_exact_mut(N::WIDTH)
+        .zip(elements)
+        .for_each(|(b, n)| n.write_ne(b));
+    w.write_all(&buf)
+}
+
+/// Reads numbers until the end of the reader, swapping their bytes if requested.
+pub fn read_elements<N: Element, R: Read>(r: &mut R, swap: bool) -> io::Result<Vec<N>> {
+    let mut buf = Vec::new();
+    r.read_to_end(&mut buf)?;
+    if buf.len() % N::WIDTH != 0 {
+        return Err(invalid_data("the file ends in the middle of a number"));
+    }
+    Ok(buf
+        .chunks_exact(N::WIDTH)
+        .map(N::read_ne)
+        .map(|n| if swap { n.swap_bytes() } else { n })
+        .collect())
+}
+
+/// Writes a complete prime file: the header, the primes, and the number the calculation arrived to.
+///
+/// # Examples
+///
+/// ```
+/// use prime::format::*;
+///
+/// let mut file = Vec::new();
+/// write_primes(&mut file, &[2u32, 3, 5, 7], 10).unwrap();
+/// assert_eq!(read_primes::<u32, _>(&mut file.as_slice()).unwrap(), (vec![2, 3, 5, 7], 10));
+/// ```
+pub fn write_primes<N: Element, W: Write>(
+    w: &mut W,
+    primes: &[N],
+    arrived_to: N,
+) -> io::Result<()> {
+    write_header::<N, _>(w)?;
+    write_elements(w, primes)?;
+    write_elements(w, &[arrived_to])
+}
+
+/// Reads a complete prime file, returning the primes and the number the calculation arrived to.
+///
+/// Returns an error of kind `InvalidData` if the header doesn't match or the file is truncated.
+///
+/// # Examples
+///
+/// ```
+/// use prime::format::*;
+///
+/// // A file written with u32 can't be read as u64.
+/// let mut file = Vec::new();
+/// write_primes(&mut file, &[2u32, 3, 5, 7], 10).unwrap();
+/// assert!(read_primes::<u64, _>(&mut file.as_slice()).is_err());
+/// ```
+pub fn read_primes<N: Element, R: Read>(r: &mut R) -> io::Result<(Vec<N>, N)> {
+    let swap = read_header::<N, _>(r)?;
+    let mut primes = read_elements(r, swap)?;
+    let arrived_to = primes
+        .pop()
+        .ok_or_else(|| invalid_data("the file doesn't say where the calculation arrived to"))?;
+    Ok((primes, arrived_to))
+}
+
+/// Returns the position in bytes of the element at the index, counting the header.
+pub fn element_offset<N: Element>(index: usize) -> usize {
+    HEADER_LEN + index * N::WIDTH
+}
+
+/// Reads a complete prime file like [`read_primes`], and then checks its contents with [`validate`].
+///
+/// Returns an error of kind `InvalidData` with the offset of the first invalid number if the primes aren't valid, or if the number the calculation arrived to isn't greater than the last prime.
+///
+/// # Examples
+///
+/// ```
+/// use prime::format::*;
+///
+/// let mut file = Vec::new();
+/// write_primes(&mut file, &[2u32, 3, 5, 7, 9], 10).unwrap();
+/// assert!(read_primes_checked::<u32, _>(&mut file.as_slice()).is_err());
+/// ```
+pub fn read_primes_checked<N: Element + Roots + Send + Sync + Display, R: Read>(
+    r: &mut R,
+) -> io::Result<(Vec<N>, N)> {
+    let (primes, arrived_to) = read_primes(r)?;
+    if let Err(e) = validate(&primes) {
+        return Err(invalid_data(format!(
+            "{} (offset {})",
+            e,
+            element_offset::<N>(e.index())
+        )));
+    }
+    if let Some(&last) = primes.last() {
+        if arrived_to <= last {
+            return Err(invalid_data(format!(
+                "the calculation arrived to {}, but the last prime is {} (offset {})",
+                arrived_to,
+                last,
+                element_offset::<N>(primes.len())
+            )));
+        }
+    }
+    Ok((primes, arrived_to))
+}
+
+pub(crate) fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(
+    error: E,
+) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_test() {
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u64, 3, 5, 7, 11], 12).unwrap();
+        assert_eq!(file.len(), HEADER_LEN + 6 * 8);
+        assert_eq!(
+            read_primes::<u64, _>(&mut file.as_slice()).unwrap(),
+            (vec![2, 3, 5, 7, 11], 12)
+        );
+
+        let mut file = Vec::new();
+        write_primes::<u16, _>(&mut file, &[], 0).unwrap();
+        assert_eq!(
+            read_primes::<u16, _>(&mut file.as_slice()).unwrap(),
+            (vec![], 0)
+        );
+    }
+
+    #[test]
+    fn endianness_test() {
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u32, 3, 5], 6).unwrap();
+
+        // Pretend the file was written on a machine with the opposite endianness.
+        file[6] = 1 - file[6];
+        file[HEADER_LEN..]
+            .chunks_exact_mut(4)
+            .for_each(|n| n.reverse());
+        assert_eq!(
+            read_primes::<u32, _>(&mut file.as_slice()).unwrap(),
+            (vec![2, 3, 5], 6)
+        );
+    }
+
+    #[test]
+    fn invalid_file_test() {
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u32, 3, 5], 6).unwrap();
+
+        let err = read_primes::<u64, _>(&mut file.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = read_primes::<u32, _>(&mut &file[..file.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let err = read_primes::<u32, _>(&mut &file[..HEADER_LEN]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut bad_version = file.clone();
+        bad_version[4] = VERSION + 1;
+        let err = read_primes::<u32, _>(&mut bad_version.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("version"));
+
+        let mut bad_endianness = file.clone();
+        bad_endianness[6] = 2;
+        let err = read_primes::<u32, _>(&mut bad_endianness.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        file[0] = b'X';
+        let err = read_primes::<u32, _>(&mut file.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn checked_test() {
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u32, 3, 5, 7], 10).unwrap();
+        assert_eq!(
+            read_primes_checked::<u32, _>(&mut file.as_slice()).unwrap(),
+            (vec![2, 3, 5, 7], 10)
+        );
+
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u32, 3, 5, 7], 7).unwrap();
+        let err = read_primes_checked::<u32, _>(&mut file.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut file = Vec::new();
+        write_primes(&mut file, &[2u32, 3, 5, 25, 29], 30).unwrap();
+        let err = read_primes_checked::<u32, _>(&mut file.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("offset 20"));
+    }
+}
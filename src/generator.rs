@@ -1,19 +1,55 @@
 use crate::calculator::*;
+use crate::sieve::*;
 use assert_unchecked::assert_unchecked;
 use num::integer::Roots;
 use num::range_step; // TODO: replace with Range trait
-use num::{CheckedAdd, FromPrimitive, Integer, ToPrimitive};
+use num::{Bounded, CheckedAdd, CheckedMul, FromPrimitive, Integer, ToPrimitive, Zero};
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::iter;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 /// Calculates roughly the number of primes that are less than n.
 ///
 /// The approximated result is always greater than the actual one
+///
+/// # Panics
+///
+/// The function panics if n can't be converted to f64 or the result can't be converted back, see [`checked_gauss_function`].
 pub fn gauss_function<N: Integer + FromPrimitive + ToPrimitive>(n: N) -> N {
+    checked_gauss_function(n).unwrap()
+}
+
+/// Like [`gauss_function`], but returns `None` instead of panicking if n can't be converted to a finite f64, or if the result can't be converted back to N.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use num::BigUint;
+///
+/// assert_eq!(checked_gauss_function(1000u32), Some(gauss_function(1000u32)));
+/// let huge: BigUint = BigUint::from(1u8) << 2000;
+/// assert_eq!(checked_gauss_function(huge), None);
+/// ```
+pub fn checked_gauss_function<N: Integer + FromPrimitive + ToPrimitive>(n: N) -> Option<N> {
     // https://mathworld.wolfram.com/PrimeNumberTheorem.html
-    let x = n.to_f64().unwrap();
+    let x = n.to_f64().filter(|x| x.is_finite())?;
     const LEGENDRE_CONSTANT: f64 = -1.08366;
-    N::from_f64((x / (x.ln() + LEGENDRE_CONSTANT)).ceil()).unwrap()
+    // Below e^1.08366 the denominator is negative, but there are no primes to count there anyway.
+    N::from_f64((x / (x.ln() + LEGENDRE_CONSTANT)).ceil().max(0.0))
+}
+
+/// The capacity to reserve for the primes less than until, or `None` if [`checked_gauss_function`] fails or doesn't fit in a usize.
+fn reservation_estimate<N: Integer + FromPrimitive + ToPrimitive>(until: N) -> Option<usize> {
+    checked_gauss_function(until)?.to_usize()
 }
 
 /// Ensures that the vector has 2 and 3 and maps them if they weren't there before.
@@ -31,11 +67,92 @@ fn get_basic_primes<N: Roots + Copy, F: FnMut(N)>(
     )
 }
 
+/// Returns the number after the last known prime, or `None` if it would overflow.
+fn next_candidate<N: Roots + Copy + CheckedAdd>(known_primes: &[N]) -> Option<N> {
+    known_primes
+        .last()
+        .cloned()
+        .unwrap_or(N::zero())
+        .checked_add(&(N::one() + N::one()))
+}
+
+/// Adds step to n, returning `None` if the result doesn't fit in the type or isn't less than until.
+///
+/// Every generator steps through its candidates, chunks and reports with it, so they all stop cleanly near the maximum value of N.
+fn checked_step<N: PartialOrd + CheckedAdd>(n: N, step: &N, until: &N) -> Option<N> {
+    n.checked_add(step).filter(|m| m < until)
+}
+
+/// Iterates from start to until, exclusive, with [`checked_step`].
+fn steps<N: Copy + PartialOrd + CheckedAdd>(
+    start: N,
+    until: N,
+    step: N,
+) -> impl Iterator<Item = N> {
+    iter::successors((start < until).then_some(start), move |&n| {
+        checked_step(n, &step, &until)
+    })
+}
+
+/// Returns the end of the chunk that starts at start, without going over until.
+fn chunk_end<N: Roots + Copy + CheckedAdd>(start: N, chunk_size: N, until: N) -> N {
+    OverflowMode::Saturating
+        .chunk_end(start, chunk_size, until)
+        .unwrap()
+}
+
+/// How the chunked generators handle a chunk whose end doesn't fit in the type, which happens when until is near its maximum value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Stops the calculation before the chunk.
+    Checked,
+    /// Clamps the end of the chunk to until, so the calculation is completed.
+    #[default]
+    Saturating,
+    /// Panics.
+    Panic,
+}
+
+impl OverflowMode {
+    /// Returns the end of the chunk that starts at start, without going over until, or `None` if the calculation must stop because the end overflows.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use prime::generator::*;
+    ///
+    /// assert_eq!(OverflowMode::Checked.chunk_end(10u8, 100, 200), Some(110));
+    /// assert_eq!(OverflowMode::Checked.chunk_end(200u8, 100, 255), None);
+    /// assert_eq!(OverflowMode::Saturating.chunk_end(200u8, 100, 255), Some(255));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// With [`OverflowMode::Panic`], the function panics if the end overflows.
+    pub fn chunk_end<N: Roots + Copy + CheckedAdd>(
+        self,
+        start: N,
+        chunk_size: N,
+        until: N,
+    ) -> Option<N> {
+        match start.checked_add(&chunk_size) {
+            Some(end) => Some(cmp::min(end, until)),
+            None => match self {
+                OverflowMode::Checked => None,
+                OverflowMode::Saturating => Some(until),
+                OverflowMode::Panic => panic!("the end of a chunk overflows"),
+            },
+        }
+    }
+}
+
 /// Check if every number from the start to the end specified is prime and returns the primes found in a Vec.
 ///
 /// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
 ///
-/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+/// The end is exclusive, like in every function of this module: only the primes less than until are calculated, so until must be at least 3 to get 2. Use [`prime_generator_inclusive`] to include until.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically (if they are less than until).
 ///
 /// # Examples
 ///
@@ -45,6 +162,10 @@ fn get_basic_primes<N: Roots + Copy, F: FnMut(N)>(
 /// // Start calculating from 3.
 /// assert_eq!(prime_generator(20, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
 ///
+/// // until isn't included.
+/// assert_eq!(prime_generator(2, Vec::new(), 0), Vec::<i32>::new());
+/// assert_eq!(prime_generator(3, Vec::new(), 0), vec![2]);
+///
 /// // Start calculating from 7, saving calculations.
 /// assert_eq!(prime_generator(20, vec![2, 3, 5, 7], 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
 ///
@@ -59,6 +180,38 @@ pub fn prime_generator<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAd
     prime_generator_map(until, known_primes, start_from, |_| {})
 }
 
+/// Like [`prime_generator`], but until is included: it's in the Vec returned if it's prime.
+///
+/// Unlike passing until + 1 to [`prime_generator`], it doesn't overflow when until is the maximum value of the type.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_generator_inclusive(19, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(prime_generator(19, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17]);
+///
+/// // 127 is prime, and it's the maximum value of i8.
+/// assert_eq!(prime_generator_inclusive(i8::MAX, Vec::new(), 0).last(), Some(&127));
+/// ```
+pub fn prime_generator_inclusive<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+) -> Vec<N> {
+    let mut primes = prime_generator(until, known_primes, start_from);
+    push_if_prime(&mut primes, until);
+    primes
+}
+
+/// Adds n to the primes if it's prime and greater than the last one. The list must contain every prime less than n.
+fn push_if_prime<N: Roots + Copy>(primes: &mut Vec<N>, n: N) {
+    if primes.last().is_none_or(|&p| p < n) && is_prime(n, primes) {
+        primes.push(n);
+    }
+}
+
 /// Check if every number from the start to the end specified is prime and returns the primes found in a Vec.
 ///
 /// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
@@ -79,31 +232,108 @@ pub fn prime_generator<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAd
 pub fn prime_generator_map<
     N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
     F: FnMut(N),
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    found: F,
+) -> Vec<N> {
+    prime_generator_map_with(until, known_primes, start_from, None, found)
+}
+
+/// How a generator reserves the memory for the primes it finds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReserveStrategy {
+    /// Reserves room for [`gauss_function`] of until before starting, so that the Vec never reallocates.
+    #[default]
+    Upfront,
+    /// Doubles the capacity of the Vec when it's full, without going over [`gauss_function`] of until.
+    ///
+    /// The Vec reallocates a few times, but a calculation that is cancelled early doesn't hold memory for primes it never found.
+    Geometric,
+}
+
+/// Makes room for one more prime, doubling the capacity of the Vec without going over the estimate.
+fn grow_geometrically<N>(primes: &mut Vec<N>, estimate: usize) {
+    if primes.len() == primes.capacity() {
+        let additional = cmp::min(primes.len(), estimate.saturating_sub(primes.len()));
+        primes.reserve_exact(additional.max(1));
+    }
+}
+
+/// Check if every number from the start to the end specified is prime and returns the primes found in a Vec, reserving its memory with the strategy specified.
+///
+/// Apart from the memory, it behaves like [`prime_generator`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let upfront = prime_generator_reserving(1000u32, Vec::new(), 0, ReserveStrategy::Upfront);
+/// let geometric = prime_generator_reserving(1000u32, Vec::new(), 0, ReserveStrategy::Geometric);
+/// assert_eq!(upfront, geometric);
+/// assert!(geometric.capacity() <= gauss_function(1000) as usize);
+/// ```
+pub fn prime_generator_reserving<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    reserve: ReserveStrategy,
+) -> Vec<N> {
+    let growth_cap = match reserve {
+        ReserveStrategy::Upfront => None,
+        // Without an estimate, the Vec just keeps doubling.
+        ReserveStrategy::Geometric => Some(reservation_estimate(until).unwrap_or(usize::MAX)),
+    };
+    prime_generator_map_with(until, known_primes, start_from, growth_cap, |_| {})
+}
+
+/// Like [`prime_generator_map`], but if growth_cap is specified the Vec grows with [`grow_geometrically`] up to it, instead of reserving everything before starting.
+fn prime_generator_map_with<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
+    F: FnMut(N),
 >(
     until: N,
     mut known_primes: Vec<N>,
     start_from: N,
+    growth_cap: Option<usize>,
     mut found: F,
 ) -> Vec<N> {
     get_basic_primes(&mut known_primes, until, &mut found);
 
-    known_primes.reserve(
-        (gauss_function(until).to_usize().unwrap()).saturating_sub(known_primes.capacity()),
-    );
+    if growth_cap.is_none() {
+        known_primes.reserve(
+            reservation_estimate(until)
+                .unwrap_or(0)
+                .saturating_sub(known_primes.capacity()),
+        );
+    }
 
     let two = N::one() + N::one();
 
-    let start_from = start_from.max(known_primes.last().cloned().unwrap_or(N::zero()) + two);
+    // There's nothing left to check if the last prime is near the maximum value of N.
+    let start_from = match next_candidate(&known_primes) {
+        Some(n) => start_from.max(n),
+        None => return known_primes,
+    };
 
     // +2 to not calculate the same number again. known_primes.last() is guaranteed to be odd.
-    range_step(start_from, until, two).for_each(|n| {
+    steps(start_from, until, two).for_each(|n| {
         unsafe {
             assert_unchecked!(
                 n >= N::one() + N::one()
                     && last_index(&n, &known_primes) <= last_index(&until, &known_primes)
             )
         };
-        if is_prime(n, known_primes.as_slice()) {
+        #[cfg(feature = "prefetch")]
+        let prime = is_prime_prefetch(n, known_primes.as_slice(), PREFETCH_DISTANCE);
+        #[cfg(not(feature = "prefetch"))]
+        let prime = is_prime(n, known_primes.as_slice());
+        if prime {
+            if let Some(cap) = growth_cap {
+                grow_geometrically(&mut known_primes, cap);
+            }
             known_primes.push(n);
             found(n);
         }
@@ -134,18 +364,39 @@ pub fn prime_generator_map<
 /// assert_eq!(par_prime_generator(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23], 0), vec![2, 3, 5, 7, 11, 13, 17, 19, 23]);
 /// ```
 pub fn par_prime_generator<
-    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync,
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
 >(
     until: N,
     known_primes: Vec<N>,
     start_from: N,
-) -> Vec<N>
-where
-    rayon::range::Iter<N>: IndexedParallelIterator<Item = N>,
-{
+) -> Vec<N> {
     par_prime_generator_map(until, known_primes, start_from, |_| {})
 }
 
+/// Like [`par_prime_generator`], but until is included: it's in the Vec returned if it's prime.
+///
+/// Unlike passing until + 1 to [`par_prime_generator`], it doesn't overflow when until is the maximum value of the type.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(par_prime_generator_inclusive(19, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(par_prime_generator_inclusive(20, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+pub fn par_prime_generator_inclusive<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+) -> Vec<N> {
+    let mut primes = par_prime_generator(until, known_primes, start_from);
+    push_if_prime(&mut primes, until);
+    primes
+}
+
 /// Parallelely check if every number from the start to the end specified is prime and returns the primes found in a Vec.
 ///
 /// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
@@ -166,35 +417,147 @@ where
 /// par_prime_generator_map(15, vec![2, 3, 5, 7, 11, 13, 17, 19], 0, |n| {println!("{}", n)}); // Prints nothing.
 /// ```
 pub fn par_prime_generator_map<
-    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync,
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+    F: Fn(N) + Send + Sync,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    found: F,
+) -> Vec<N> {
+    par_prime_generator_map_len(until, known_primes, start_from, usize::MAX, found)
+}
+
+/// Parallelely check if every number from the start to the end specified is prime and returns the primes found in a Vec.
+///
+/// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
+///
+/// Checking a candidate costs more the bigger it is, so when the range is split evenly the threads that get the end of the range have more work to do. Rayon already balances this by letting idle threads steal work, but only from pieces that are big enough to be split again: by splitting the range in pieces of at most `max_len` candidates, the work is spread more evenly at the cost of some overhead. See the `generation` benchmark to compare different lengths.
+///
+/// Before checking in parallel, only one number at a time will be checked until the square root of max.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(par_prime_generator_balanced(20, Vec::new(), 0, 2), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if max_len is 0.
+pub fn par_prime_generator_balanced<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    max_len: usize,
+) -> Vec<N> {
+    assert!(max_len != 0);
+    par_prime_generator_map_len(until, known_primes, start_from, max_len, |_| {})
+}
+
+fn par_prime_generator_map_len<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
     F: Fn(N) + Send + Sync,
 >(
     until: N,
     mut known_primes: Vec<N>,
     start_from: N,
+    max_len: usize,
     found: F,
-) -> Vec<N>
-where
-    rayon::range::Iter<N>: IndexedParallelIterator<Item = N>,
-{
+) -> Vec<N> {
     get_basic_primes(&mut known_primes, until, &found);
 
     known_primes.reserve(
-        (gauss_function(until).to_usize().unwrap()).saturating_sub(known_primes.capacity()),
+        reservation_estimate(until)
+            .unwrap_or(0)
+            .saturating_sub(known_primes.capacity()),
     );
 
     let sqr = until.sqrt();
 
     // Because we can't modify the vector during the calculation, known_primes must be filled (single_threaded) until the square root of chunk so that we don't need to modify it.
     // However, if the vector is filled enough this step can be skipped and we can start from the last number in the vector.
-    let mut start_from =
-        start_from.max(known_primes.last().cloned().unwrap_or(N::zero()) + N::one() + N::one());
-    if sqr > start_from {
-        known_primes = prime_generator_map(sqr, known_primes, start_from, &found);
-        start_from = sqr;
+    let mut start_from = match next_candidate(&known_primes) {
+        Some(n) => start_from.max(n),
+        None => return known_primes,
+    };
+    if sqr >= start_from {
+        known_primes = prime_generator_map(sqr + N::one(), known_primes, start_from, &found);
+        start_from = sqr + N::one();
     }
 
-    par_prime_generator_map_nosetup(until, known_primes, start_from, found)
+    par_prime_generator_map_nosetup(until, known_primes, start_from, max_len, found)
+}
+
+/// Returns how many of 2 and 3 `get_basic_primes` would add to a Vec of length `len`.
+fn basic_primes_added<N: Roots>(len: usize, until: N) -> usize {
+    [N::one() + N::one(), N::one() + N::one() + N::one()]
+        .into_iter()
+        .skip(len)
+        .filter(|n| *n < until)
+        .count()
+}
+
+/// Check if every number from the start to the end specified is prime and returns the primes found in a Vec.
+///
+/// Unlike [`prime_generator`], 2 and 3 are not added to the Vec if they weren't already there. They are still used as divisors during the calculation, so the result is correct even if the Vec sent is empty.
+///
+/// Because of this, the Vec returned can't be passed back to the other generators as `known_primes` if it doesn't start with 2 and 3.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// // Only odd primes from 5 onward.
+/// assert_eq!(prime_generator_raw(30, Vec::new(), 5), vec![5, 7, 11, 13, 17, 19, 23, 29]);
+///
+/// // Primes already in the Vec are kept.
+/// assert_eq!(prime_generator_raw(20, vec![2, 3, 5, 7], 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+pub fn prime_generator_raw<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+) -> Vec<N> {
+    let from = known_primes.len();
+    let added = basic_primes_added(from, until);
+    let mut primes = prime_generator(until, known_primes, start_from);
+    primes.drain(from..from + added);
+    primes
+}
+
+/// Parallelely check if every number from the start to the end specified is prime and returns the primes found in a Vec.
+///
+/// Unlike [`par_prime_generator`], 2 and 3 are not added to the Vec if they weren't already there. They are still used as divisors during the calculation, so the result is correct even if the Vec sent is empty.
+///
+/// Because of this, the Vec returned can't be passed back to the other generators as `known_primes` if it doesn't start with 2 and 3.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(par_prime_generator_raw(30, Vec::new(), 5), vec![5, 7, 11, 13, 17, 19, 23, 29]);
+/// ```
+pub fn par_prime_generator_raw<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+) -> Vec<N> {
+    let from = known_primes.len();
+    let added = basic_primes_added(from, until);
+    let mut primes = par_prime_generator(until, known_primes, start_from);
+    primes.drain(from..from + added);
+    primes
 }
 
 fn oddize<N: Integer>(n: N) -> N {
@@ -206,35 +569,106 @@ fn oddize<N: Integer>(n: N) -> N {
     }
 }
 
-fn par_prime_generator_map_nosetup<N: Roots + Copy + Send + Sync, F: Fn(N) + Send + Sync>(
+fn par_prime_generator_map_nosetup<
+    N: Roots + Copy + Send + Sync + ParCandidates,
+    F: Fn(N) + Send + Sync,
+>(
     until: N,
     mut known_primes: Vec<N>,
     start_from: N,
+    max_len: usize,
     found: F,
-) -> Vec<N>
-where
-    rayon::range::Iter<N>: IndexedParallelIterator<Item = N>,
-{
-    let start_from = oddize(start_from);
-    let mut new_primes: Vec<N> = (start_from..until)
-        .into_par_iter()
-        .step_by(2)
-        .filter(|n| {
-            unsafe {
-                assert_unchecked!(
-                    *n >= N::one() + N::one()
-                        && last_index(n, known_primes.as_slice())
-                            <= last_index(&until, &known_primes)
-                )
-            };
-            is_prime(*n, known_primes.as_slice())
-        })
-        .inspect(|n| found(*n))
-        .collect();
+) -> Vec<N> {
+    let mut new_primes = par_find_primes(until, &known_primes, start_from, max_len, found);
     known_primes.append(new_primes.as_mut());
     known_primes
 }
 
+/// Parallelely finds the primes from start_from to until, using only the known primes as divisors, splitting the candidates in pieces of at most max_len.
+///
+/// The known primes must reach the square root of until.
+pub(crate) fn par_find_primes<
+    N: Roots + Copy + Send + Sync + ParCandidates,
+    F: Fn(N) + Send + Sync,
+>(
+    until: N,
+    known_primes: &[N],
+    start_from: N,
+    max_len: usize,
+    found: F,
+) -> Vec<N> {
+    N::par_filter_odd(oddize(start_from), until, max_len, |n| {
+        unsafe {
+            assert_unchecked!(
+                n >= N::one() + N::one()
+                    && last_index(&n, known_primes) <= last_index(&until, known_primes)
+            )
+        };
+        if is_prime(n, known_primes) {
+            found(n);
+            true
+        } else {
+            false
+        }
+    })
+}
+
+/// A number whose candidates can be checked in parallel.
+///
+/// Rayon can split ranges of numbers up to 32 bits (and `usize`) efficiently. For 64-bit and 128-bit numbers, the range is split in blocks of candidates manually.
+pub trait ParCandidates: Sized {
+    /// Parallelely filters every other number from start (which must be odd) to until, returning the ones that pass in order.
+    ///
+    /// The candidates are split in pieces of at most max_len.
+    fn par_filter_odd<F: Fn(Self) -> bool + Sync>(
+        start: Self,
+        until: Self,
+        max_len: usize,
+        filter: F,
+    ) -> Vec<Self>;
+}
+
+macro_rules! impl_par_candidates_indexed {
+    ($($t:ty),*) => {
+        $(
+            impl ParCandidates for $t {
+                fn par_filter_odd<F: Fn(Self) -> bool + Sync>(start: Self, until: Self, max_len: usize, filter: F) -> Vec<Self> {
+                    (start..until)
+                        .into_par_iter()
+                        .step_by(2)
+                        .with_max_len(max_len)
+                        .filter(|n| filter(*n))
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_par_candidates_blocks {
+    ($($t:ty),*) => {
+        $(
+            impl ParCandidates for $t {
+                fn par_filter_odd<F: Fn(Self) -> bool + Sync>(start: Self, until: Self, max_len: usize, filter: F) -> Vec<Self> {
+                    // Even, so that every block starts from an odd number.
+                    let block = 2 * max_len.min(2048) as $t;
+                    let blocks: Vec<$t> = range_step(start, until, block).collect();
+                    blocks
+                        .into_par_iter()
+                        .flat_map_iter(|b| {
+                            range_step(b, b.saturating_add(block).min(until), 2)
+                                .filter(|n| filter(*n))
+                        })
+                        .collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_par_candidates_indexed!(u8, u16, u32, usize, i8, i16, i32, isize);
+impl_par_candidates_blocks!(u64, u128, i64, i128);
+
 /// Parallelely check if every number from the start to the end specified is prime and returns the primes found in a Vec.
 ///
 /// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
@@ -252,9 +686,9 @@ where
 /// ```
 /// use prime::generator::*;
 ///
-/// par_prime_generator_map_chunks(15, Vec::new(), 0, 5, |n| {println!("{}", n)}, |_, _| true); // Prints 2, 3, 5, 7, 11, 13.
-/// par_prime_generator_map_chunks(15, vec![2, 3, 5], 0, 5, |n| {println!("{}", n)}, |_, _| true); // Prints 7, 11, 13.
-/// par_prime_generator_map_chunks(15, vec![2, 3, 5, 7, 11, 13, 17, 19], 0, 5, |n| {println!("{}", n)}, |_, _| true); // Prints nothing.
+/// par_prime_generator_map_chunks(15, Vec::new(), 0, 5, |_, _| true, |arr| {println!("{:?}", arr)}); // Prints 2, 3, 5, 7, 11, 13.
+/// par_prime_generator_map_chunks(15, vec![2, 3, 5], 0, 5, |_, _| true, |arr| {println!("{:?}", arr)}); // Prints 7, 11, 13.
+/// par_prime_generator_map_chunks(15, vec![2, 3, 5, 7, 11, 13, 17, 19], 0, 5, |_, _| true, |arr| {println!("{:?}", arr)}); // Prints nothing.
 /// ```
 ///
 /// # Panics
@@ -264,122 +698,2842 @@ where
 /// ```should_panic
 /// use prime::generator::*;
 ///
-/// par_prime_generator_map_chunks(15, Vec::new(), 0, 0, |n| {println!("{}", n)}, |_, _| true); // Panics!
+/// par_prime_generator_map_chunks(15, Vec::new(), 0, 0, |_, _| true, |arr| {println!("{:?}", arr)}); // Panics!
 /// ```
 pub fn par_prime_generator_map_chunks<
-    N: Roots + Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync,
+    N: Roots + Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
     F: FnMut(N, N) -> bool,
     G: FnMut(&[N]),
 >(
     until: N,
-    mut known_primes: Vec<N>,
+    known_primes: Vec<N>,
     start_from: N,
     chunk_size: N,
     mut pre_cycle: F,
-    mut post_cycle: G,
-) -> Vec<N>
-where
-    rayon::range::Iter<N>: IndexedParallelIterator<Item = N>,
-{
-    assert!(!chunk_size.is_zero());
+    post_cycle: G,
+) -> Vec<N> {
+    par_prime_generator_map_chunks_controlled(
+        until,
+        known_primes,
+        ChunkOptions {
+            start_from,
+            chunk_size,
+        },
+        |start, end| {
+            if pre_cycle(start, end) {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(StopReason::Cancelled)
+            }
+        },
+        post_cycle,
+    )
+    .0
+}
 
-    let end = cmp::min(start_from + chunk_size, until);
-    if !pre_cycle(start_from, end) {
-        return known_primes;
+/// Parallelely check if every number from the start to the end specified is prime one chunk at a time, like [`par_prime_generator_map_chunks`], unless `cancel` becomes true.
+///
+/// It's the parallel counterpart of [`cancellable_generator`]: the flag is checked before every chunk, and if the calculation is cancelled, the primes less than the start of the next chunk are returned as an error along with it.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let cancel = AtomicBool::new(false);
+/// assert_eq!(par_prime_generator_cancellable(20, Vec::new(), ChunkOptions::default().chunk_size(5), &cancel), Ok(vec![2, 3, 5, 7, 11, 13, 17, 19]));
+///
+/// let cancel = AtomicBool::new(true);
+/// let partial = par_prime_generator_cancellable(20, vec![2, 3, 5, 7], ChunkOptions::default().chunk_size(5), &cancel).unwrap_err();
+/// assert_eq!(partial, PartialResult { primes: vec![2, 3, 5, 7], reached: 9, reason: StopReason::Cancelled });
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the chunk size of the options is 0.
+pub fn par_prime_generator_cancellable<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    mut known_primes: Vec<N>,
+    options: ChunkOptions<N>,
+    cancel: &AtomicBool,
+) -> Result<Vec<N>, PartialResult<N>> {
+    get_basic_primes(&mut known_primes, until, |_| {});
+    // Start from the first candidate like the sequential version, so every known prime is less than where it stops.
+    let start_from = match next_candidate(&known_primes) {
+        Some(n) => options.start_from.max(n),
+        None => return Ok(known_primes),
+    };
+    let mut reached = start_from;
+    let (mut primes, reason) = par_prime_generator_map_chunks_controlled(
+        until,
+        known_primes,
+        options.start_from(start_from),
+        |start, _| {
+            if cancel.load(Ordering::Relaxed) {
+                reached = start;
+                ControlFlow::Break(StopReason::Cancelled)
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        |_| {},
+    );
+    match reason {
+        Some(reason) => {
+            primes.truncate(primes.partition_point(|&p| p < reached));
+            Err(PartialResult {
+                primes,
+                reached,
+                reason,
+            })
+        }
+        None => Ok(primes),
     }
+}
 
-    let from = known_primes.len();
-    known_primes = par_prime_generator(end, known_primes, start_from);
-    post_cycle(&known_primes[from..]);
+/// The chunk size used by a default [`ChunkOptions`], or the maximum value of the type if it's smaller.
+pub const DEFAULT_CHUNK_SIZE: u32 = 1_000_000;
 
-    let start_from =
-        end.max(known_primes.last().cloned().unwrap_or(N::zero()) + N::one() + N::one());
+/// Where a chunked calculation starts and how big its chunks are, for [`par_prime_generator_map_chunks_with`] and the other chunked generators.
+///
+/// Grouping the two numbers in a struct with named fields prevents them from being swapped, as they have the same type.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let options = ChunkOptions::default().chunk_size(10u32).start_from(5);
+/// assert_eq!(options, ChunkOptions { start_from: 5, chunk_size: 10 });
+///
+/// assert_eq!(ChunkOptions::<u8>::default(), ChunkOptions { start_from: 0, chunk_size: 255 });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkOptions<N> {
+    /// The number the calculation starts from, if it's higher than the last known prime.
+    pub start_from: N,
+    /// How many numbers are checked in parallel before `pre_cycle` and `post_cycle` are called again. It must not be 0.
+    pub chunk_size: N,
+}
 
-    for start in range_step(start_from, until, chunk_size) {
-        let end = cmp::min(start + chunk_size, until);
-        if !pre_cycle(start, end) {
-            break;
-        }
+impl<N> ChunkOptions<N> {
+    /// Sets the number the calculation starts from.
+    pub fn start_from(self, start_from: N) -> Self {
+        ChunkOptions { start_from, ..self }
+    }
 
-        let from = known_primes.len();
-        known_primes = par_prime_generator_map_nosetup(end, known_primes, start, |_| {});
-        post_cycle(&known_primes[from..]);
+    /// Sets the size of the chunks.
+    pub fn chunk_size(self, chunk_size: N) -> Self {
+        ChunkOptions { chunk_size, ..self }
     }
-    known_primes
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<N: Zero + FromPrimitive + Bounded> Default for ChunkOptions<N> {
+    fn default() -> Self {
+        ChunkOptions {
+            start_from: N::zero(),
+            chunk_size: N::from_u32(DEFAULT_CHUNK_SIZE).unwrap_or_else(N::max_value),
+        }
+    }
+}
 
-    #[test]
+/// Like [`par_prime_generator_map_chunks`], but with the start and the chunk size in a [`ChunkOptions`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let primes = par_prime_generator_map_chunks_with(20, Vec::new(), ChunkOptions::default().chunk_size(5), |_, _| true, |_| {});
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the chunk size is 0.
+pub fn par_prime_generator_map_chunks_with<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+    F: FnMut(N, N) -> bool,
+    G: FnMut(&[N]),
+>(
+    until: N,
+    known_primes: Vec<N>,
+    options: ChunkOptions<N>,
+    pre_cycle: F,
+    post_cycle: G,
+) -> Vec<N> {
+    par_prime_generator_map_chunks(
+        until,
+        known_primes,
+        options.start_from,
+        options.chunk_size,
+        pre_cycle,
+        post_cycle,
+    )
+}
+
+/// Why a calculation stopped before reaching the end, as reported by the `pre_cycle` of [`par_prime_generator_map_chunks_controlled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// The calculation was cancelled, for example by the user.
+    Cancelled,
+    /// Enough primes were found.
+    TargetCount,
+    /// The time or the memory allowed ran out.
+    Budget,
+    /// The end of a chunk doesn't fit in the type, see [`OverflowMode::Checked`].
+    Overflow,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "the calculation was cancelled"),
+            StopReason::TargetCount => write!(f, "enough primes were found"),
+            StopReason::Budget => write!(f, "the budget ran out"),
+            StopReason::Overflow => write!(f, "the end of a chunk overflows"),
+        }
+    }
+}
+
+/// Like [`par_prime_generator_map_chunks`], but `pre_cycle` returns why the calculation should stop instead of just `false`.
+///
+/// The reason is returned along with the primes, or `None` if the calculation reached until.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use std::cell::Cell;
+/// use std::ops::ControlFlow;
+///
+/// // Stop after the chunk in which at least 5 primes have been found.
+/// let count = Cell::new(0);
+/// let (primes, reason) = par_prime_generator_map_chunks_controlled(
+///     100,
+///     Vec::new(),
+///     ChunkOptions::default().chunk_size(10),
+///     |_, _| if count.get() >= 5 { ControlFlow::Break(StopReason::TargetCount) } else { ControlFlow::Continue(()) },
+///     |arr| count.set(count.get() + arr.len()),
+/// );
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(reason, Some(StopReason::TargetCount));
+///
+/// let (primes, reason) = par_prime_generator_map_chunks_controlled(20, Vec::new(), ChunkOptions::default().chunk_size(10), |_, _| ControlFlow::Continue(()), |_| {});
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(reason, None);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the chunk size of the options is 0.
+pub fn par_prime_generator_map_chunks_controlled<
+    N: Roots + Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+    F: FnMut(N, N) -> ControlFlow<StopReason>,
+    G: FnMut(&[N]),
+>(
+    until: N,
+    mut known_primes: Vec<N>,
+    options: ChunkOptions<N>,
+    mut pre_cycle: F,
+    mut post_cycle: G,
+) -> (Vec<N>, Option<StopReason>) {
+    let ChunkOptions {
+        start_from,
+        chunk_size,
+    } = options;
+    assert!(!chunk_size.is_zero());
+
+    let end = chunk_end(start_from, chunk_size, until);
+    if let ControlFlow::Break(reason) = pre_cycle(start_from, end) {
+        return (known_primes, Some(reason));
+    }
+
+    let from = known_primes.len();
+    known_primes = par_prime_generator(end, known_primes, start_from);
+    post_cycle(&known_primes[from..]);
+
+    let start_from = match next_candidate(&known_primes) {
+        Some(n) => end.max(n),
+        None => return (known_primes, None),
+    };
+
+    for start in steps(start_from, until, chunk_size) {
+        let end = chunk_end(start, chunk_size, until);
+        if let ControlFlow::Break(reason) = pre_cycle(start, end) {
+            return (known_primes, Some(reason));
+        }
+
+        let from = known_primes.len();
+        // The first chunk could have ended before 2 or 3.
+        get_basic_primes(&mut known_primes, end, |_| {});
+        let start = match next_candidate(&known_primes) {
+            Some(n) => start.max(n),
+            None => break,
+        };
+        known_primes =
+            par_prime_generator_map_nosetup(end, known_primes, start, usize::MAX, |_| {});
+        post_cycle(&known_primes[from..]);
+    }
+    (known_primes, None)
+}
+
+/// Parallelely check if every number from the start to the end specified is prime one chunk at a time, like [`par_prime_generator_map_chunks`], handling the chunks whose end doesn't fit in the type as the mode says.
+///
+/// With [`OverflowMode::Checked`], the calculation stops before such a chunk, and the primes found so far are returned as an error along with the start of the chunk.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let primes = par_prime_generator_chunked(255u8, Vec::new(), ChunkOptions::default().chunk_size(100), OverflowMode::Saturating).unwrap();
+/// assert_eq!(primes.last(), Some(&251));
+///
+/// let partial = par_prime_generator_chunked(255u8, Vec::new(), ChunkOptions::default().chunk_size(100), OverflowMode::Checked).unwrap_err();
+/// assert_eq!(partial.reached, 200);
+/// assert_eq!(partial.primes.last(), Some(&199));
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the chunk size of the options is 0, or if the end of a chunk overflows with [`OverflowMode::Panic`].
+pub fn par_prime_generator_chunked<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    options: ChunkOptions<N>,
+    overflow: OverflowMode,
+) -> Result<Vec<N>, PartialResult<N>> {
+    let mut reached = options.start_from;
+    let (primes, reason) = par_prime_generator_map_chunks_controlled(
+        until,
+        known_primes,
+        options,
+        |start, _| match overflow.chunk_end(start, options.chunk_size, until) {
+            Some(_) => ControlFlow::Continue(()),
+            None => {
+                reached = start;
+                ControlFlow::Break(StopReason::Overflow)
+            }
+        },
+        |_| {},
+    );
+    match reason {
+        Some(reason) => Err(PartialResult {
+            primes,
+            reached,
+            reason,
+        }),
+        None => Ok(primes),
+    }
+}
+
+/// Calculates the primes less than until and returns the numbers that are not prime in a Vec, excluding 0 and 1.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(composites_below(15, Vec::new()), vec![4, 6, 8, 9, 10, 12, 14]);
+/// ```
+pub fn composites_below<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+) -> Vec<N> {
+    let primes = prime_generator(until, known_primes, N::zero());
+    let mut primes = primes.iter().peekable();
+
+    let two = N::one() + N::one();
+    num::range(two + two, until)
+        .filter(|n| {
+            while primes.next_if(|&p| p < n).is_some() {}
+            primes.peek() != Some(&n)
+        })
+        .collect()
+}
+
+/// The primes returned by [`prime_generator_detailed`], along with some information about the calculation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenResult<N> {
+    /// Every prime known after the calculation, including the ones that were sent.
+    pub primes: Vec<N>,
+    /// How many primes in the Vec weren't sent.
+    pub count_new: usize,
+    /// Every prime less than this number is in the Vec.
+    pub computed_up_to: N,
+}
+
+/// Check if every number from the start to the end specified is prime and returns the primes found, along with how many of them are new and the number the calculation arrived to.
+///
+/// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically, and they will be counted as new.
+///
+/// If start_from skips some numbers after the last known prime, computed_up_to is the first of them: the primes found after it are in the Vec, but the ones before them aren't.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let result = prime_generator_detailed(20, vec![2, 3, 5, 7], 0);
+/// assert_eq!(result.primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(result.count_new, 4);
+/// assert_eq!(result.computed_up_to, 20);
+///
+/// // The Vec already went further than until.
+/// let result = prime_generator_detailed(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23], 0);
+/// assert_eq!(result.count_new, 0);
+/// assert_eq!(result.computed_up_to, 24);
+/// ```
+pub fn prime_generator_detailed<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+) -> GenResult<N> {
+    let from = known_primes.len();
+    let mut primes = known_primes;
+    get_basic_primes(&mut primes, until, |_| {});
+    // The primes before the next candidate are all known, but if start_from is after it the ones in between are skipped.
+    let skipped_from = next_candidate(&primes).filter(|&n| start_from > n);
+    let primes = prime_generator(until, primes, start_from);
+    let computed_up_to = match (primes.last(), skipped_from) {
+        (Some(&last), _) if last >= until => last.checked_add(&N::one()).unwrap_or(last),
+        (_, Some(n)) => n.min(until),
+        _ => until,
+    };
+    GenResult {
+        count_new: primes.len() - from,
+        primes,
+        computed_up_to,
+    }
+}
+
+/// Check if every number from the start to the end specified is prime and returns the primes found in a Vec.
+///
+/// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
+///
+/// You can specify the behavior when a prime is found, and `on_progress` is called with every multiple of `report_every` that the calculation goes past. Unlike `found`, it's called at regular intervals no matter how many primes there are.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// prime_generator_report(5000, Vec::new(), 0, 1000, |_| {}, |n| {println!("Arrived to {}", n)}); // Prints 1000, 2000, 3000, 4000.
+/// ```
+///
+/// # Panics
+///
+/// The function panics if report_every is 0.
+pub fn prime_generator_report<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + CheckedMul,
+    F: FnMut(N),
+    G: FnMut(N),
+>(
+    until: N,
+    mut known_primes: Vec<N>,
+    start_from: N,
+    report_every: N,
+    mut found: F,
+    mut on_progress: G,
+) -> Vec<N> {
+    assert!(!report_every.is_zero());
+
+    let start_from = match next_candidate(&known_primes) {
+        Some(n) => start_from.max(n),
+        None => start_from,
+    };
+    let first_report = (start_from / report_every + N::one()).checked_mul(&report_every);
+
+    if let Some(first_report) = first_report {
+        for report in steps(first_report, until, report_every) {
+            known_primes = prime_generator_map(report, known_primes, start_from, &mut found);
+            on_progress(report);
+        }
+    }
+    prime_generator_map(until, known_primes, start_from, &mut found)
+}
+
+/// Check if every number until the end specified is prime and returns the primes found in a Vec, along with a Vec of the gaps between every new prime and the one before it.
+///
+/// The gaps are calculated during the generation, and there is one for every prime that wasn't in the Vec sent. The gap of 2 is 0.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_generator_with_gaps(20, Vec::new()), (vec![2, 3, 5, 7, 11, 13, 17, 19], vec![0, 1, 2, 2, 4, 2, 4, 2]));
+/// assert_eq!(prime_generator_with_gaps(20, vec![2, 3, 5, 7]), (vec![2, 3, 5, 7, 11, 13, 17, 19], vec![4, 2, 4, 2]));
+/// ```
+pub fn prime_generator_with_gaps<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+) -> (Vec<N>, Vec<N>) {
+    let mut gaps = Vec::new();
+    let mut previous = known_primes.last().cloned();
+    let primes = prime_generator_map(until, known_primes, N::zero(), |n| {
+        gaps.push(previous.map_or(N::zero(), |p| n - p));
+        previous = Some(n);
+    });
+    (primes, gaps)
+}
+
+/// Returns the first prime greater than or equal to start that satisfies the predicate.
+///
+/// Every candidate is checked with [`ignorant_is_prime`], so no list of known primes is needed. There is no upper bound: if no prime satisfies the predicate, the function never returns.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// // The first prime from 20 onward that ends with 7.
+/// assert_eq!(first_prime_where(|p| p % 10 == 7, 20), 37);
+///
+/// // The first prime p from 100 onward such that p + 2 is prime too.
+/// assert_eq!(first_prime_where(|p| prime::calculator::ignorant_is_prime(p + 2, &[]), 100), 101);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if no prime that fits in the type satisfies the predicate.
+pub fn first_prime_where<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
+    P: Fn(N) -> bool,
+>(
+    pred: P,
+    start: N,
+) -> N {
+    let two = N::one() + N::one();
+    if start <= two && pred(two) {
+        return two;
+    }
+
+    let mut n = oddize(start.max(two + N::one()));
+    loop {
+        if pred(n) && ignorant_is_prime(n, &[]) {
+            return n;
+        }
+        n = n
+            .checked_add(&two)
+            .expect("no prime that fits in the type satisfies the predicate");
+    }
+}
+
+/// The primes found by a calculation that stopped before reaching the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialResult<N> {
+    /// Every prime less than `reached`, including the ones that were sent.
+    pub primes: Vec<N>,
+    /// The number the calculation arrived to. Pass it as `start_from` to continue.
+    pub reached: N,
+    /// Why the calculation stopped.
+    pub reason: StopReason,
+}
+
+impl<N: fmt::Display> fmt::Display for PartialResult<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.reason, self.reached)
+    }
+}
+
+impl<N: fmt::Debug + fmt::Display> Error for PartialResult<N> {}
+
+/// How many numbers are checked between looking at the cancellation flag.
+const CANCEL_CHECK_INTERVAL: u32 = 1 << 16;
+
+/// Check if every number from the start to the end specified is prime and returns the primes found in a Vec, unless `cancel` becomes true.
+///
+/// The flag is checked every few thousand numbers. If the calculation is cancelled, the primes found so far are returned as an error, along with the number the calculation arrived to.
+///
+/// The latest prime in the list list is used as a starting point if it is higher than the start supplied.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let cancel = AtomicBool::new(false);
+/// assert_eq!(cancellable_generator(20, Vec::new(), 0, &cancel), Ok(vec![2, 3, 5, 7, 11, 13, 17, 19]));
+///
+/// let cancel = AtomicBool::new(true);
+/// let partial = cancellable_generator(20, vec![2, 3, 5, 7], 0, &cancel).unwrap_err();
+/// assert_eq!(partial, PartialResult { primes: vec![2, 3, 5, 7], reached: 9, reason: StopReason::Cancelled });
+/// ```
+pub fn cancellable_generator<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    cancel: &AtomicBool,
+) -> Result<Vec<N>, PartialResult<N>> {
+    cancellable_generator_reserving(
+        until,
+        known_primes,
+        start_from,
+        cancel,
+        ReserveStrategy::Upfront,
+    )
+}
+
+/// Like [`cancellable_generator`], but reserving the memory for the primes with the strategy specified.
+///
+/// With [`ReserveStrategy::Geometric`], a calculation that is cancelled early only holds memory for the primes it found.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let cancel = AtomicBool::new(true);
+/// let partial = cancellable_generator_reserving(1u32 << 24, Vec::new(), 0, &cancel, ReserveStrategy::Geometric).unwrap_err();
+/// assert_eq!(partial.primes, vec![2, 3]);
+/// assert!(partial.primes.capacity() < 1000);
+/// ```
+pub fn cancellable_generator_reserving<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    start_from: N,
+    cancel: &AtomicBool,
+    reserve: ReserveStrategy,
+) -> Result<Vec<N>, PartialResult<N>> {
+    cancellable_generator_map(until, known_primes, start_from, cancel, reserve, |_| {})
+}
+
+pub(crate) fn cancellable_generator_map<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd,
+    F: FnMut(N),
+>(
+    until: N,
+    mut known_primes: Vec<N>,
+    start_from: N,
+    cancel: &AtomicBool,
+    reserve: ReserveStrategy,
+    mut found: F,
+) -> Result<Vec<N>, PartialResult<N>> {
+    get_basic_primes(&mut known_primes, until, &mut found);
+    let estimate = reservation_estimate(until);
+    let growth_cap = match reserve {
+        ReserveStrategy::Upfront => {
+            known_primes.reserve(estimate.unwrap_or(0).saturating_sub(known_primes.len()));
+            None
+        }
+        ReserveStrategy::Geometric => Some(estimate.unwrap_or(usize::MAX)),
+    };
+    let mut start = match next_candidate(&known_primes) {
+        Some(n) => start_from.max(n),
+        None => return Ok(known_primes),
+    };
+    let interval = N::from_u32(CANCEL_CHECK_INTERVAL).unwrap_or(until);
+
+    while start < until {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(PartialResult {
+                primes: known_primes,
+                reached: start,
+                reason: StopReason::Cancelled,
+            });
+        }
+        let end = chunk_end(start, interval, until);
+        known_primes = prime_generator_map_with(end, known_primes, start, growth_cap, &mut found);
+        start = end;
+    }
+    Ok(known_primes)
+}
+
+/// Check if every number until the end specified is prime and returns the primes greater than or equal to start in a Vec.
+///
+/// The Vec sent is only used to save calculations, and it must be ordered. Every prime less than start is removed from the result.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(primes_in_range(10, 30, Vec::new()), vec![11, 13, 17, 19, 23, 29]);
+/// assert_eq!(primes_in_range(10, 30, vec![2, 3, 5, 7, 11, 13]), vec![11, 13, 17, 19, 23, 29]);
+/// ```
+pub fn primes_in_range<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    start: N,
+    until: N,
+    known_primes: Vec<N>,
+) -> Vec<N> {
+    let mut primes = prime_generator(until, known_primes, N::zero());
+    let below_start = primes.partition_point(|&p| p < start);
+    let below_until = primes.partition_point(|&p| p < until);
+    primes.truncate(below_until);
+    primes.drain(..below_start);
+    primes
+}
+
+/// Returns every prime with exactly d decimal digits in a Vec, using [`primes_in_range`].
+///
+/// The Vec sent is only used to save calculations, and it must be ordered.
+///
+/// If 10^d doesn't fit in N, the primes from 10^(d - 1) up to the maximum value of N are returned. If not even 10^(d - 1) fits, there are no numbers with d digits in N, and the Vec is empty.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(primes_with_digits(1, Vec::<u32>::new()), vec![2, 3, 5, 7]);
+/// assert_eq!(primes_with_digits(2, Vec::<u32>::new()).len(), 21);
+/// // The three-digit primes that fit in an i8.
+/// assert_eq!(primes_with_digits(3, Vec::<i8>::new()), vec![101, 103, 107, 109, 113, 127]);
+/// ```
+pub fn primes_with_digits<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + CheckedMul + Bounded,
+>(
+    d: u32,
+    known_primes: Vec<N>,
+) -> Vec<N> {
+    let ten = N::from_u8(10).unwrap();
+    let Some(start) = d
+        .checked_sub(1)
+        .and_then(|e| num::checked_pow(ten, e as usize))
+    else {
+        return Vec::new();
+    };
+    match start.checked_mul(&ten) {
+        Some(until) => primes_in_range(start, until, known_primes),
+        None => {
+            let mut primes = prime_generator_inclusive(N::max_value(), known_primes, N::zero());
+            let below_start = primes.partition_point(|&p| p < start);
+            primes.drain(..below_start);
+            primes
+        }
+    }
+}
+
+/// Returns every prime less than until along with its binary Hamming weight, the number of bits set to 1.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(primes_with_popcount(10u32), vec![(2, 1), (3, 2), (5, 2), (7, 3)]);
+/// ```
+pub fn primes_with_popcount<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+) -> Vec<(N, u32)> {
+    prime_generator(until, Vec::new(), N::zero())
+        .into_iter()
+        .map(|p| (p, p.to_u128().unwrap().count_ones()))
+        .collect()
+}
+
+/// Returns every prime less than until with exactly k bits set to 1.
+///
+/// Every prime but 2 is odd, so k = 1 only gives 2, and the primes with k = 2 are 3 and the Fermat primes 2^(2^m) + 1.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(primes_with_exact_popcount(10u32, 2), vec![3, 5]);
+/// assert_eq!(primes_with_exact_popcount(100u32, 2), vec![3, 5, 17]);
+/// ```
+pub fn primes_with_exact_popcount<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    k: u32,
+) -> Vec<N> {
+    primes_with_popcount(until)
+        .into_iter()
+        .filter_map(|(p, weight)| (weight == k).then_some(p))
+        .collect()
+}
+
+/// Appends every prime less than until that is greater than the last prime of the list, and returns the primes that were added.
+///
+/// Candidates start right after the last prime of the list, and every prime found becomes a divisor for the following candidates, so the list only needs to be complete up to its last element: it doesn't have to reach the square root of until.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let mut primes = vec![2, 3, 5, 7];
+/// assert_eq!(extend_primes(&mut primes, 20), [11, 13, 17, 19]);
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+///
+/// // Nothing is added if the list already goes past until.
+/// assert_eq!(extend_primes(&mut primes, 15), [0; 0]);
+/// ```
+pub fn extend_primes<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    primes: &mut Vec<N>,
+    until: N,
+) -> &[N] {
+    let old_len = primes.len();
+    *primes = prime_generator(until, std::mem::take(primes), N::zero());
+    &primes[old_len..]
+}
+
+/// The largest until for which [`best_primes_below`] uses the sieve: its bit buffer takes 16 MiB.
+///
+/// The sieve is faster than trial division by more than an order of magnitude at every size, so the limit only bounds the memory it takes: above it, the segmented sieve is used instead.
+pub const SIEVE_LIMIT: usize = 1 << 27;
+
+/// Returns every prime less than until in a Vec, picking the fastest strategy for the arguments:
+///
+/// - If the last prime of the list is at least half of until, most of the work is already done, so the list is continued with [`prime_generator`].
+/// - If until is at most [`SIEVE_LIMIT`], the list is ignored and every prime is found with [`crate::sieve::sieve_into_bits`], which is faster than trial division as long as its buffer fits in memory comfortably.
+/// - Otherwise the list is ignored and every prime is found with [`crate::sieve::segmented_sieve_map`], which only keeps a window of [`crate::sieve::DEFAULT_SEGMENT_BYTES`] in memory besides the primes.
+/// - If until doesn't even fit in u64, the list is continued with [`par_prime_generator`].
+///
+/// The Vec sent must be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(best_primes_below(20u32, Vec::new()), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(best_primes_below(20u32, vec![2, 3, 5, 7, 11, 13, 17, 19, 23]), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+pub fn best_primes_below<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+) -> Vec<N> {
+    best_primes_below_with(until, known_primes, SIEVE_LIMIT)
+}
+
+fn best_primes_below_with<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+    known_primes: Vec<N>,
+    sieve_limit: usize,
+) -> Vec<N> {
+    let two = N::one() + N::one();
+    let mut primes = match known_primes.last() {
+        Some(&last) if last >= until => known_primes,
+        Some(&last) if last >= until / two => prime_generator(until, known_primes, N::zero()),
+        _ => match (until.to_usize(), until.to_u64()) {
+            (Some(u), _) if u <= sieve_limit => sieve_primes(u),
+            (_, Some(u)) => segmented_primes(u),
+            _ => par_prime_generator(until, known_primes, N::zero()),
+        },
+    };
+    primes.truncate(primes.partition_point(|&p| p < until));
+    primes
+}
+
+/// Collects the primes less than until found by [`crate::sieve::segmented_sieve_map`].
+fn segmented_primes<N: FromPrimitive>(until: u64) -> Vec<N> {
+    let mut primes = Vec::new();
+    segmented_sieve_map(until, DEFAULT_SEGMENT_BYTES, |segment| {
+        primes.extend(segment.iter().map(|&p| N::from_u64(p).unwrap()))
+    });
+    primes
+}
+
+/// Collects the primes less than until found by [`crate::sieve::sieve_into_bits`].
+fn sieve_primes<N: FromPrimitive>(until: usize) -> Vec<N> {
+    let mut bits = vec![0; bits_required(until)];
+    sieve_into_bits(until, &mut bits);
+    let mut primes = Vec::new();
+    for (i, &word) in bits.iter().enumerate() {
+        let mut word = word;
+        while word != 0 {
+            primes.push(N::from_usize(i * 64 + word.trailing_zeros() as usize).unwrap());
+            word &= word - 1;
+        }
+    }
+    primes
+}
+
+/// Like [`par_prime_generator_map_chunks`], but resumes a previous calculation from the start_from of the options, the number it arrived to, instead of from the last prime in the list.
+///
+/// The list must contain every prime less than that number. The primes greater than or equal to it, like the ones of a chunk that was interrupted halfway, are discarded and calculated again, so that none is skipped.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// // The calculation arrived to 20, but it had already found 29 in the next chunk.
+/// let options = ChunkOptions::default().chunk_size(10).start_from(20);
+/// let primes = par_prime_generator_map_chunks_from(40, vec![2, 3, 5, 7, 11, 13, 17, 19, 29], options, |_, _| true, |_| {});
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the chunk size of the options is 0.
+pub fn par_prime_generator_map_chunks_from<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+    F: FnMut(N, N) -> bool,
+    G: FnMut(&[N]),
+>(
+    until: N,
+    mut known_primes: Vec<N>,
+    options: ChunkOptions<N>,
+    pre_cycle: F,
+    post_cycle: G,
+) -> Vec<N> {
+    let resume_from = options.start_from;
+    known_primes.truncate(known_primes.partition_point(|&p| p < resume_from));
+    // Even numbers other than 2 aren't prime, and the candidates must start from an odd number.
+    par_prime_generator_map_chunks_with(
+        until,
+        known_primes,
+        options.start_from(oddize(resume_from)),
+        pre_cycle,
+        post_cycle,
+    )
+}
+
+/// Returns the first N primes, calculated at compile time when used in a constant.
+///
+/// Every candidate is checked by dividing it by the primes found before it, so it's only meant for small tables.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// const FIRST_TEN: [u32; 10] = const_primes();
+/// assert_eq!(FIRST_TEN, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+/// ```
+pub const fn const_primes<const N: usize>() -> [u32; N] {
+    let mut primes = [0; N];
+    let mut len = 0;
+    let mut candidate = 2;
+    while len < N {
+        let mut i = 0;
+        let mut prime = true;
+        while i < len && primes[i] * primes[i] <= candidate {
+            if candidate % primes[i] == 0 {
+                prime = false;
+                break;
+            }
+            i += 1;
+        }
+        if prime {
+            primes[len] = candidate;
+            len += 1;
+        }
+        candidate += 1;
+    }
+    primes
+}
+
+/// Check if every number until the end specified is prime and returns the primes found in a Vec, along with a map from every prime to its position in the Vec, starting from 1.
+///
+/// The Vec answers "which is the nth prime" and the map answers "which prime is p", like [`crate::calculator::prime_index`] but without a binary search. The map takes several times the memory of the Vec, so for big lists the binary search is usually the better choice.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let (primes, ranks) = prime_generator_with_inverse(20, Vec::new());
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(ranks[&13], 6);
+/// assert_eq!(ranks.get(&12), None);
+/// ```
+pub fn prime_generator_with_inverse<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Hash,
+>(
+    until: N,
+    known_primes: Vec<N>,
+) -> (Vec<N>, HashMap<N, usize>) {
+    let mut ranks: HashMap<N, usize> = known_primes
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| (p, i + 1))
+        .collect();
+    let primes = prime_generator_map(until, known_primes, N::zero(), |n| {
+        let rank = ranks.len() + 1;
+        ranks.insert(n, rank);
+    });
+    (primes, ranks)
+}
+
+/// The error returned by [`prime_generator_bucketed`] when asked for 0 buckets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoBuckets;
+
+impl fmt::Display for NoBuckets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the primes can't be split in 0 buckets")
+    }
+}
+
+impl Error for NoBuckets {}
+
+/// Check if every number until the end specified is prime and returns the primes found split in num_buckets Vecs, each prime p going in the one at index p % num_buckets.
+///
+/// Every prime is put in its bucket as soon as it's found. Every bucket is ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_generator_bucketed(20, 3), Ok(vec![vec![3], vec![7, 13, 19], vec![2, 5, 11, 17]]));
+/// assert_eq!(prime_generator_bucketed(20, 0), Err(NoBuckets));
+/// ```
+pub fn prime_generator_bucketed<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    num_buckets: usize,
+) -> Result<Vec<Vec<N>>, NoBuckets> {
+    if num_buckets == 0 {
+        return Err(NoBuckets);
+    }
+    let mut buckets = vec![Vec::new(); num_buckets];
+    // If num_buckets doesn't fit in N, every prime is its own remainder.
+    let modulus = N::from_usize(num_buckets);
+    prime_generator_map(until, Vec::new(), N::zero(), |p| {
+        let i = modulus.map_or(p, |m| p % m).to_usize().unwrap();
+        buckets[i].push(p);
+    });
+    Ok(buckets)
+}
+
+/// How many candidates [`prime_generator_timed`] checks between two looks at the clock.
+pub const TIMED_CHECK_INTERVAL: usize = 1024;
+
+/// Checks every number from start_from upward until the deadline passes, and returns the primes found in a Vec.
+///
+/// There's no upper bound: the primes returned are every prime from start_from to wherever the calculation arrived, without gaps. The clock is read once every [`TIMED_CHECK_INTERVAL`] candidates, so the deadline can be overshot by the time it takes to check them.
+///
+/// The divisors up to the square root of the candidates are calculated along the way, so no list of known primes is needed.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use std::time::{Duration, Instant};
+///
+/// let primes = prime_generator_timed(Instant::now() + Duration::from_millis(10), 0u64);
+/// assert_eq!(primes[..4], [2, 3, 5, 7]);
+///
+/// // The time is already over.
+/// assert_eq!(prime_generator_timed(Instant::now(), 0u64), Vec::<u64>::new());
+/// ```
+pub fn prime_generator_timed<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    deadline: Instant,
+    start_from: N,
+) -> Vec<N> {
+    let mut primes = Vec::new();
+    for (checked, (n, prime)) in checked_candidates(start_from).enumerate() {
+        if checked % TIMED_CHECK_INTERVAL == 0 && Instant::now() >= deadline {
+            break;
+        }
+        if prime {
+            primes.push(n);
+        }
+    }
+    primes
+}
+
+/// Checks every number from start_from upward, stopping at the maximum value of the type, and yields each of them with whether it's prime.
+///
+/// The divisors up to the square root of the candidates are calculated along the way.
+fn checked_candidates<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    start_from: N,
+) -> impl Iterator<Item = (N, bool)> {
+    let two = N::one() + N::one();
+    let mut divisors = Vec::new();
+    let mut next = Some(cmp::max(start_from, two));
+    iter::from_fn(move || {
+        let n = next?;
+        next = n.checked_add(&if n.is_even() { N::one() } else { two });
+        // By Bertrand's postulate, there's a prime between the square root and its double.
+        if divisors.last().is_none_or(|&p| p < n.sqrt()) {
+            extend_primes(&mut divisors, n.sqrt() * two + two);
+        }
+        Some((n, is_prime(n, &divisors)))
+    })
+}
+
+/// Returns the primes less than until one at a time behind a trait object, so that it can be stored without knowing its type, or every prime up to the maximum value of the type if until is `None`.
+///
+/// The primes are calculated lazily, only when they are requested.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(boxed_primes(Some(20u32)).count(), 8);
+/// assert_eq!(boxed_primes::<u32>(None).take(5).collect::<Vec<_>>(), vec![2, 3, 5, 7, 11]);
+///
+/// let sources: Vec<Box<dyn Iterator<Item = u64>>> = vec![boxed_primes(Some(10)), boxed_primes(None)];
+/// ```
+pub fn boxed_primes<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + 'static>(
+    until: Option<N>,
+) -> Box<dyn Iterator<Item = N>> {
+    let primes = checked_candidates(N::zero()).filter_map(|(n, prime)| prime.then_some(n));
+    match until {
+        Some(until) => Box::new(primes.take_while(move |&p| p < until)),
+        None => Box::new(primes),
+    }
+}
+
+/// How many primes [`prime_generator_verified`] checks again.
+pub const VERIFY_SAMPLES: usize = 10_000;
+
+/// Returns every prime less than until like [`best_primes_below`], after checking them with [`verify_primes`], which uses an independent primality test.
+///
+/// If there are at most [`VERIFY_SAMPLES`] primes, all of them and the numbers between them are checked; otherwise, that many random ones.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_generator_verified(20u32), Ok(vec![2, 3, 5, 7, 11, 13, 17, 19]));
+/// ```
+pub fn prime_generator_verified<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + ParCandidates,
+>(
+    until: N,
+) -> Result<Vec<N>, VerificationError<N>> {
+    let primes = best_primes_below(until, Vec::new());
+    verify_primes(&primes, until, VERIFY_SAMPLES)?;
+    Ok(primes)
+}
+
+/// Returns a number greater than the nth prime, starting from 1.
+pub(crate) fn nth_prime_bound(n: usize) -> u64 {
+    if n < 6 {
+        return 13;
+    }
+    // Rosser's theorem: p(n) < n (ln n + ln ln n) for n >= 6.
+    let x = n as f64;
+    (x * (x.ln() + x.ln().ln())).ceil() as u64
+}
+
+/// Makes sure that the divisors reach the square root of n, copying and extending them with [`extend_primes`] if they don't.
+fn divisors_reaching<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    divisors: &mut Cow<[N]>,
+    n: N,
+) {
+    let two = N::one() + N::one();
+    // By Bertrand's postulate, there's a prime between the square root and its double.
+    if divisors.last().is_none_or(|&p| p < n.sqrt()) {
+        extend_primes(divisors.to_mut(), n.sqrt() * two + two);
+    }
+}
+
+/// Calculates if a candidate of [`next_prime`] or [`prev_prime`] is prime: with [`mont_is_prime`] if it fits in a u64, otherwise with [`is_prime`], extending the divisors first if they don't reach its square root.
+fn is_prime_candidate<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    candidate: N,
+    divisors: &mut Cow<[N]>,
+) -> bool {
+    if let Some(n) = candidate.to_u64() {
+        return mont_is_prime(n);
+    }
+    divisors_reaching(divisors, candidate);
+    is_prime(candidate, divisors)
+}
+
+/// Returns the smallest prime strictly greater than n, or `None` if it doesn't fit in N.
+///
+/// The candidates that fit in a u64 are checked with [`mont_is_prime`], which doesn't need the known primes. The others are checked with [`is_prime`]: if the known primes don't reach the square root of a candidate, they are copied and extended as needed, so the list sent can be short or even empty. It's required for the list to be ordered and to contain every prime up to its last element.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(checked_next_prime(96, &[2, 3, 5, 7]), Some(97));
+/// // The greatest prime that fits in a u64.
+/// assert_eq!(checked_next_prime(u64::MAX - 59, &[]), Some(u64::MAX - 58));
+/// assert_eq!(checked_next_prime(u64::MAX - 58, &[]), None);
+/// ```
+pub fn checked_next_prime<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: N,
+    known_primes: &[N],
+) -> Option<N> {
+    let two = N::one() + N::one();
+    if n < two {
+        return Some(two);
+    }
+    let mut divisors = Cow::Borrowed(known_primes);
+    let mut candidate = n;
+    loop {
+        candidate = candidate.checked_add(&N::one())?;
+        if is_prime_candidate(candidate, &mut divisors) {
+            return Some(candidate);
+        }
+    }
+}
+
+/// Returns the smallest prime strictly greater than n, like [`checked_next_prime`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(next_prime(96, &[2, 3, 5, 7]), 97);
+/// assert_eq!(next_prime(97, &[2, 3, 5, 7]), 101);
+/// // 1000001 is 101 * 9901.
+/// assert_eq!(next_prime(1_000_000, &[2, 3, 5]), 1_000_003);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the next prime doesn't fit in N.
+pub fn next_prime<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: N,
+    known_primes: &[N],
+) -> N {
+    checked_next_prime(n, known_primes).expect("the next prime doesn't fit in the type")
+}
+
+/// Returns the greatest prime strictly less than n, or `None` if n is at most 2.
+///
+/// Like in [`checked_next_prime`], the candidates that fit in a u64 don't need the known primes, and for the others they are copied and extended if they don't reach the square root of n. It's required for the list to be ordered and to contain every prime up to its last element.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prev_prime(98, &[2, 3, 5, 7]), Some(97));
+/// assert_eq!(prev_prime(97, &[2, 3, 5, 7]), Some(89));
+/// assert_eq!(prev_prime(2, &[2, 3, 5, 7]), None);
+/// ```
+pub fn prev_prime<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    n: N,
+    known_primes: &[N],
+) -> Option<N> {
+    let two = N::one() + N::one();
+    if n <= two {
+        return None;
+    }
+    let mut divisors = Cow::Borrowed(known_primes);
+    let mut candidate = n - N::one();
+    while !is_prime_candidate(candidate, &mut divisors) {
+        candidate = candidate - N::one();
+    }
+    Some(candidate)
+}
+
+/// Returns the nth prime, starting from 1, generating the primes with [`prime_generator`].
+///
+/// The primes are first generated up to a bound from Rosser's theorem, which should already contain the nth prime; if it doesn't, like when the bound is rounded down by the conversion to f64 or doesn't fit in N, the bound is doubled until it does, and the primes already found are kept. The bound stops growing at the maximum value of N.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(nth_prime::<u32>(1), 2);
+/// assert_eq!(nth_prime::<u32>(1000), 7919);
+/// assert_eq!(nth_prime::<u8>(54), 251);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if n is 0, or if the nth prime doesn't fit in N.
+pub fn nth_prime<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + CheckedMul + Bounded,
+>(
+    n: usize,
+) -> N {
+    assert!(n != 0, "the primes are counted from 1");
+    let two = N::one() + N::one();
+    let mut until = N::from_u64(nth_prime_bound(n)).unwrap_or_else(N::max_value);
+    let mut primes = Vec::new();
+    loop {
+        primes = prime_generator(until, primes, N::zero());
+        if primes.len() >= n {
+            return primes[n - 1];
+        }
+        if until == N::max_value() {
+            // until itself is never generated.
+            if primes.len() == n - 1 && ignorant_is_prime(until, &primes) {
+                return until;
+            }
+            panic!("the prime number {} doesn't fit in the type", n);
+        }
+        until = until.checked_mul(&two).unwrap_or_else(N::max_value);
+    }
+}
+
+/// Returns the primes at the indices requested, starting from 1, in the same order as the indices.
+///
+/// The primes are found once with [`best_primes_below`], up to a bound of the largest index, which is much cheaper than looking for every index separately.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(nth_primes(&[1, 6, 10001]), vec![2, 13, 104743]);
+/// assert_eq!(nth_primes(&[3, 1, 3]), vec![5, 2, 5]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if one of the indices is 0.
+pub fn nth_primes(indices: &[usize]) -> Vec<u64> {
+    assert!(!indices.contains(&0), "the primes are counted from 1");
+    let Some(&max) = indices.iter().max() else {
+        return Vec::new();
+    };
+    let primes = best_primes_below(nth_prime_bound(max), Vec::new());
+    indices.iter().map(|&i| primes[i - 1]).collect()
+}
+
+/// Returns the sum of the first n primes as a u128, which can't overflow for any n that can be calculated.
+///
+/// The primes are found with [`segmented_sieve_map`] up to a bound of the nth prime, and they are added up without being kept in memory.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(sum_of_first_primes_u128(10), 129);
+/// assert_eq!(sum_of_first_primes_u128(0), 0);
+/// ```
+pub fn sum_of_first_primes_u128(n: usize) -> u128 {
+    if n == 0 {
+        return 0;
+    }
+    let mut remaining = n;
+    let mut sum = 0;
+    segmented_sieve_map(nth_prime_bound(n), DEFAULT_SEGMENT_BYTES, |primes| {
+        let taken = cmp::min(remaining, primes.len());
+        sum += primes[..taken].iter().map(|&p| p as u128).sum::<u128>();
+        remaining -= taken;
+    });
+    sum
+}
+
+/// Returns the sum of the first n primes, or `None` if it doesn't fit in a u64.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(checked_sum_of_first_primes(10), Some(129));
+/// ```
+pub fn checked_sum_of_first_primes(n: usize) -> Option<u64> {
+    u64::try_from(sum_of_first_primes_u128(n)).ok()
+}
+
+/// Returns the sum of the first n primes.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(sum_of_first_primes(10), 129); // 2 + 3 + 5 + 7 + 11 + 13 + 17 + 19 + 23 + 29
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the sum doesn't fit in a u64: use [`checked_sum_of_first_primes`] or [`sum_of_first_primes_u128`] for such n.
+pub fn sum_of_first_primes(n: usize) -> u64 {
+    checked_sum_of_first_primes(n).expect("the sum doesn't fit in a u64")
+}
+
+/// Returns the primes less than until as a one-dimensional [`ndarray`] array, ready for numerical code that works on arrays.
+///
+/// The primes are found with [`best_primes_below`], and the Vec is turned into the array without being copied.
+///
+/// Requires the `ndarray` feature.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let primes = primes_as_array(20);
+/// assert_eq!(primes, ndarray::arr1(&[2, 3, 5, 7, 11, 13, 17, 19]));
+/// assert_eq!(primes.sum(), 77);
+/// ```
+#[cfg(feature = "ndarray")]
+pub fn primes_as_array(until: u64) -> ndarray::Array1<u64> {
+    ndarray::Array1::from_vec(best_primes_below(until, Vec::new()))
+}
+
+/// Returns the last k primes less than until, in order, without keeping the ones before them.
+///
+/// Only the primes up to the square root of until are stored as divisors, and the primes found are pushed into a ring buffer of k elements that drops the oldest one when it's full, so the memory used is O(k + √until) no matter how many primes there are.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_generator_tail(100u32, 3), vec![83, 89, 97]);
+/// assert_eq!(prime_generator_tail(10u32, 100), vec![2, 3, 5, 7]);
+/// ```
+pub fn prime_generator_tail<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    k: usize,
+) -> Vec<N> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut tail = VecDeque::with_capacity(k);
+    let mut push = |p| {
+        if tail.len() == k {
+            tail.pop_front();
+        }
+        tail.push_back(p);
+    };
+
+    let sqr = until.sqrt();
+    let divisors = prime_generator(sqr + N::one(), Vec::new(), N::zero());
+    divisors.iter().for_each(|&p| push(p));
+    num::range(sqr + N::one(), until)
+        .filter(|&n| is_prime(n, &divisors))
+        .for_each(push);
+    tail.into()
+}
+
+/// Sets every flag to whether range_start plus its index is prime, checking with [`is_prime`], so that the primality of a window of numbers can be stored in an existing buffer without allocating.
+///
+/// The list must be ordered and reach the square root of the last number of the window.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let mut flags = [true; 10];
+/// mark_primes(90, &mut flags, &[2u32, 3, 5, 7]);
+/// assert_eq!(flags, [false, false, false, false, false, false, false, true, false, false]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if a number of the window doesn't fit in N.
+pub fn mark_primes<N: Roots + FromPrimitive + Copy>(
+    range_start: usize,
+    flags: &mut [bool],
+    known_primes: &[N],
+) {
+    flags.iter_mut().enumerate().for_each(|(i, flag)| {
+        *flag = is_prime(N::from_usize(range_start + i).unwrap(), known_primes);
+    });
+}
+
+/// Like [`mark_primes`], but the flags are split between threads.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// let mut flags = vec![false; 1000];
+/// par_mark_primes(1_000_000, &mut flags, &prime_generator(1001u32, Vec::new(), 0));
+/// assert_eq!(flags.iter().filter(|&&f| f).count(), 75);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if a number of the window doesn't fit in N.
+pub fn par_mark_primes<N: Roots + FromPrimitive + Copy + Send + Sync>(
+    range_start: usize,
+    flags: &mut [bool],
+    known_primes: &[N],
+) {
+    flags.par_iter_mut().enumerate().for_each(|(i, flag)| {
+        *flag = is_prime(N::from_usize(range_start + i).unwrap(), known_primes);
+    });
+}
+
+/// Returns the primes less than until that are at least min_gap greater than the previous prime.
+///
+/// 2 has no previous prime, so it's never returned.
+///
+/// The Vec sent is only used to save calculations, and it must be ordered.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(primes_after_gap(100u32, 6, Vec::new()), vec![29, 37, 53, 59, 67, 79, 89, 97]);
+/// assert_eq!(primes_after_gap(10u32, 1, Vec::new()), vec![3, 5, 7]);
+/// ```
+pub fn primes_after_gap<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    min_gap: N,
+    known_primes: Vec<N>,
+) -> Vec<N> {
+    let primes = prime_generator(until, known_primes, N::zero());
+    primes
+        .windows(2)
+        .filter(|w| w[1] < until && w[1] - w[0] >= min_gap)
+        .map(|w| w[1])
+        .collect()
+}
+
+/// Counts the primes less than n by generating them with [`prime_generator`].
+///
+/// Unlike [`prime_pi`], it doesn't need a list, but it calculates every prime up to n each time.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(prime_pi_generate(100u32), 25);
+/// assert_eq!(prime_pi_generate(2u32), 0);
+/// ```
+pub fn prime_pi_generate<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(n: N) -> N {
+    N::from_usize(prime_generator(n, Vec::new(), N::zero()).len()).unwrap()
+}
+
+/// Returns every prime less than until, like [`prime_generator`] with an empty list, but found with a segmented Sieve of Eratosthenes instead of trial division.
+///
+/// The primes up to the square root of until are sieved first, and then the rest of the range is crossed out one window of [`DEFAULT_SEGMENT_BYTES`] at a time, see [`segmented_sieve_map`]. This takes O(n log log n) operations instead of O(n √n / ln n), so it's much faster for big ranges.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(sieve_generator(20u32), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(sieve_generator(1_000_000u32), prime_generator(1_000_000u32, Vec::new(), 0));
+/// ```
+///
+/// # Panics
+///
+/// The function panics if until doesn't fit in a u64.
+pub fn sieve_generator<N: Roots + FromPrimitive + ToPrimitive + Copy>(until: N) -> Vec<N> {
+    if until < N::zero() {
+        return Vec::new();
+    }
+    let until_u64 = until.to_u64().expect("until must fit in a u64");
+    let mut primes = Vec::with_capacity(reservation_estimate(until).unwrap_or(0));
+    segmented_sieve_map(until_u64, DEFAULT_SEGMENT_BYTES, |found| {
+        primes.extend(found.iter().map(|&p| N::from_u64(p).unwrap()));
+    });
+    primes
+}
+
+/// The numbers less than 30 that are coprime with it: every prime greater than 5 is congruent to one of them modulo 30.
+const WHEEL_30: [u8; 8] = [1, 7, 11, 13, 17, 19, 23, 29];
+
+/// Check if every number from the start to the end specified is prime and returns the primes found in a Vec, like [`prime_generator`], but only looking at the numbers coprime with 30.
+///
+/// [`prime_generator`] skips the even numbers, so it still checks the multiples of 3 and 5, which are 7 in every 15 odd numbers; a wheel of 30 only visits the 8 residues coprime with 30 in every 30 numbers. 5, which the wheel skips, is added separately.
+///
+/// The result is the same as [`prime_generator`] whenever start_from is 0 or odd. Since [`is_prime`] already rejects the multiples of 3 and 5 with a single modulo, the time saved is the loop and the modulo of the skipped candidates, which the `generation` benchmark measures at about a third.
+///
+/// The Vec sent must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+///
+/// assert_eq!(wheel_generator(20, Vec::new(), 0), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(wheel_generator(100_000u32, Vec::new(), 0), prime_generator(100_000, Vec::new(), 0));
+/// ```
+pub fn wheel_generator<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd>(
+    until: N,
+    mut known_primes: Vec<N>,
+    start_from: N,
+) -> Vec<N> {
+    get_basic_primes(&mut known_primes, until, |_| {});
+    known_primes.reserve(
+        reservation_estimate(until)
+            .unwrap_or(0)
+            .saturating_sub(known_primes.capacity()),
+    );
+
+    let start_from = match next_candidate(&known_primes) {
+        Some(n) => start_from.max(n),
+        None => return known_primes,
+    };
+    let five = N::from_u8(5).unwrap();
+    if start_from <= five && start_from.is_odd() && five < until {
+        known_primes.push(five);
+    }
+
+    let thirty = N::from_u8(30).unwrap();
+    let from = start_from.max(N::from_u8(7).unwrap());
+    let mut base = from - from % thirty;
+    let mut i = WHEEL_30
+        .iter()
+        .position(|&r| N::from_u8(r).unwrap() >= from % thirty)
+        .unwrap_or(WHEEL_30.len());
+    loop {
+        if i == WHEEL_30.len() {
+            i = 0;
+            base = match base.checked_add(&thirty) {
+                Some(base) => base,
+                None => break,
+            };
+        }
+        let n = match base.checked_add(&N::from_u8(WHEEL_30[i]).unwrap()) {
+            Some(n) if n < until => n,
+            _ => break,
+        };
+        if is_prime(n, &known_primes) {
+            known_primes.push(n);
+        }
+        i += 1;
+    }
+    known_primes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
     fn generator_test() {
-        assert_eq!(prime_generator(0, Vec::new(), 0), Vec::new());
+        assert_eq!(prime_generator(0, Vec::new(), 0), Vec::<u32>::new());
+        assert_eq!(
+            prime_generator(20, Vec::new(), 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            prime_generator(20, vec![2, 3, 5, 7], 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            prime_generator(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+    }
+
+    #[test]
+    fn multi_generator_test() {
+        assert_eq!(par_prime_generator(0, Vec::new(), 0), Vec::<u32>::new());
+        assert_eq!(
+            par_prime_generator(20, Vec::new(), 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            par_prime_generator(20, vec![2, 3, 5, 7], 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            par_prime_generator(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        // The square root of until is prime, so it must be a divisor as well.
+        assert_eq!(
+            par_prime_generator(30, Vec::new(), 0),
+            prime_generator(30, Vec::new(), 0)
+        );
+        assert_eq!(
+            par_prime_generator(122, Vec::new(), 0),
+            prime_generator(122, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn raw_generator_test() {
+        assert_eq!(prime_generator_raw(0, Vec::new(), 0), Vec::<u32>::new());
+        assert_eq!(prime_generator_raw(3, Vec::new(), 0), Vec::<u32>::new());
+        assert_eq!(
+            prime_generator_raw(30, Vec::new(), 5),
+            vec![5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(
+            prime_generator_raw(20, vec![2, 3, 5, 7], 0),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            par_prime_generator_raw(30, Vec::new(), 5),
+            vec![5, 7, 11, 13, 17, 19, 23, 29]
+        );
+        assert_eq!(
+            par_prime_generator_raw(100, Vec::new(), 0),
+            prime_generator(100, Vec::new(), 0)[2..]
+        );
+    }
+
+    #[test]
+    fn par_chunks_generator_test() {
+        assert_eq!(
+            par_prime_generator_map_chunks(0, Vec::new(), 0, 10, |_, _| true, |_| {}),
+            Vec::<u32>::new()
+        );
+        assert_eq!(
+            par_prime_generator_map_chunks(0, vec![2, 3, 5], 0, 10, |_, _| true, |_| {}),
+            vec![2, 3, 5]
+        );
+        assert_eq!(
+            par_prime_generator_map_chunks(20, Vec::new(), 0, 10, |_, _| true, |_| {}),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            par_prime_generator_map_chunks(20, vec![2, 3, 5, 7], 0, 10, |_, _| true, |_| {}),
+            vec![2, 3, 5, 7, 11, 13, 17, 19]
+        );
+        assert_eq!(
+            par_prime_generator_map_chunks(
+                20,
+                vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29],
+                0,
+                10,
+                |_, _| true,
+                |_| {},
+            ),
+            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+        );
+    }
+
+    #[test]
+    fn balanced_generator_test() {
+        let primes = prime_generator(100_000, Vec::new(), 0);
+        for max_len in [1, 7, 1000, usize::MAX] {
+            assert_eq!(
+                par_prime_generator_balanced(100_000, Vec::new(), 0, max_len),
+                primes
+            );
+            assert!(
+                par_prime_generator_balanced::<u64>(100_000, Vec::new(), 0, max_len)
+                    .into_iter()
+                    .eq(primes.iter().map(|&p| p as u64))
+            );
+        }
+    }
+
+    #[test]
+    fn wide_types_test() {
+        let primes = prime_generator::<u32>(10_000, Vec::new(), 0);
+
+        let primes_u128 = par_prime_generator::<u128>(1000, Vec::new(), 0);
+        assert_eq!(primes_u128.len(), 168);
+        assert!(primes_u128
+            .iter()
+            .zip(&primes)
+            .all(|(&a, &b)| a == b as u128));
+
+        // Make sure more than one block is used.
+        assert!(par_prime_generator::<u64>(10_000, Vec::new(), 0)
+            .into_iter()
+            .eq(primes.iter().map(|&p| p as u64)));
+        assert!(par_prime_generator::<i128>(10_000, Vec::new(), 0)
+            .into_iter()
+            .eq(primes.iter().map(|&p| p as i128)));
+        assert!(par_prime_generator_map_chunks::<i64, _, _>(
+            10_000,
+            Vec::new(),
+            0,
+            999,
+            |_, _| true,
+            |_| {}
+        )
+        .into_iter()
+        .eq(primes.iter().map(|&p| p as i64)));
+    }
+
+    #[test]
+    fn small_types_test() {
+        let primes_u8 = prime_generator::<u8>(255, Vec::new(), 0);
+        assert_eq!(primes_u8.len(), 54);
+        assert_eq!(primes_u8.last(), Some(&251));
+
+        let primes_i8 = prime_generator::<i8>(127, Vec::new(), 0);
+        assert_eq!(primes_i8.len(), 30);
+        // The next candidate after 127 doesn't fit in an i8.
+        let mut with_127 = primes_i8.clone();
+        with_127.push(127);
+        assert_eq!(prime_generator::<i8>(127, with_127.clone(), 0), with_127);
+        assert_eq!(
+            par_prime_generator::<i8>(127, with_127.clone(), 0),
+            with_127
+        );
+
+        // The chunks go over the maximum value of N.
+        assert_eq!(
+            par_prime_generator_map_chunks::<u8, _, _>(
+                255,
+                Vec::new(),
+                0,
+                200,
+                |_, _| true,
+                |_| {}
+            ),
+            primes_u8
+        );
+        assert_eq!(
+            par_prime_generator_map_chunks::<u16, _, _>(
+                u16::MAX,
+                Vec::new(),
+                0,
+                30_000,
+                |_, _| true,
+                |_| {},
+            )
+            .last(),
+            Some(&65521)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn par_chunks_panic() {
+        par_prime_generator_map_chunks(20, Vec::new(), 0, 0, |_, _| true, |_| {});
+    }
+
+    #[test]
+    fn composites_test() {
+        assert_eq!(composites_below(0, Vec::new()), Vec::<u32>::new());
+        assert_eq!(composites_below(5, Vec::new()), vec![4]);
+        assert_eq!(
+            composites_below(15, Vec::new()),
+            vec![4, 6, 8, 9, 10, 12, 14]
+        );
+        assert_eq!(
+            composites_below(15, vec![2, 3, 5, 7, 11, 13, 17]),
+            vec![4, 6, 8, 9, 10, 12, 14]
+        );
+        assert_eq!(composites_below(1000, Vec::new()).len(), 1000 - 2 - 168);
+    }
+
+    #[test]
+    fn detailed_generator_test() {
+        let result = prime_generator_detailed(20, Vec::new(), 0);
+        assert_eq!(result.primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+        assert_eq!(result.count_new, 8);
+        assert_eq!(result.computed_up_to, 20);
+
+        let known = prime_generator(50, Vec::new(), 0);
+        let result = prime_generator_detailed(100, known.clone(), 0);
+        assert_eq!(
+            result.count_new,
+            result
+                .primes
+                .iter()
+                .filter(|p| known.binary_search(p).is_err())
+                .count()
+        );
+        assert_eq!(result.count_new, 25 - 15);
+        assert_eq!(result.computed_up_to, 100);
+
+        let result = prime_generator_detailed(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], 0);
+        assert_eq!(result.count_new, 0);
+        assert_eq!(result.computed_up_to, 30);
+
+        // The numbers between the known primes and start_from are skipped, so the list is only complete up to them.
+        let result = prime_generator_detailed(100, Vec::new(), 50);
+        assert_eq!(result.computed_up_to, 5);
+        let result = prime_generator_detailed(100, vec![2, 3, 5, 7], 51);
+        assert_eq!(result.computed_up_to, 9);
+        let result = prime_generator_detailed(100, vec![2, 3, 5, 7], 9);
+        assert_eq!(result.computed_up_to, 100);
+        let result = prime_generator_detailed(20, vec![2, 3, 5, 7], 50);
+        assert_eq!(result.computed_up_to, 9);
+        let result = prime_generator_detailed(8, vec![2, 3, 5, 7], 50);
+        assert_eq!(result.computed_up_to, 8);
+    }
+
+    #[test]
+    fn report_generator_test() {
+        let mut reports = Vec::new();
+        let mut found = 0;
+        let primes = prime_generator_report(
+            5000,
+            Vec::new(),
+            0,
+            1000,
+            |_| found += 1,
+            |n| reports.push(n),
+        );
+        assert_eq!(primes, prime_generator(5000, Vec::new(), 0));
+        assert_eq!(found, 669);
+        assert_eq!(reports, vec![1000, 2000, 3000, 4000]);
+
+        let mut reports = Vec::new();
+        let known = prime_generator(2500, Vec::new(), 0);
+        let primes = prime_generator_report(5001, known, 0, 1000, |_| {}, |n| reports.push(n));
+        assert_eq!(primes, prime_generator(5001, Vec::new(), 0));
+        assert_eq!(reports, vec![3000, 4000, 5000]);
+    }
+
+    #[test]
+    fn gaps_generator_test() {
+        let (primes, gaps) = prime_generator_with_gaps(10_000, Vec::new());
+        assert_eq!(primes, prime_generator(10_000, Vec::new(), 0));
+        assert_eq!(gaps.len(), primes.len());
+        assert_eq!(gaps[0], 0);
+        let reconstructed: Vec<u32> = gaps
+            .iter()
+            .scan(2, |p, gap| {
+                *p += gap;
+                Some(*p)
+            })
+            .collect();
+        assert_eq!(reconstructed, primes);
+
+        let (primes, gaps) = prime_generator_with_gaps(100, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+        assert_eq!(gaps.len(), primes.len() - 8);
+        assert_eq!(gaps[0], 4);
+
+        assert_eq!(
+            prime_generator_with_gaps(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23]),
+            (vec![2, 3, 5, 7, 11, 13, 17, 19, 23], vec![])
+        );
+    }
+
+    #[test]
+    fn first_prime_where_test() {
+        assert_eq!(first_prime_where(|p| p % 10 == 7, 20), 37);
+        assert_eq!(first_prime_where(|p| p % 10 == 7, 37), 37);
+        assert_eq!(first_prime_where(|_| true, 0), 2);
+        assert_eq!(first_prime_where(|p| p > 2, 0), 3);
+        assert_eq!(first_prime_where(|_| true, 24), 29);
+        assert_eq!(first_prime_where(|p| p % 10 == 7, 1_000_000u64), 1_000_037);
+        assert_eq!(first_prime_where(|_| true, 250u8), 251);
+        assert!(std::panic::catch_unwind(|| first_prime_where(|p| p > 251, 0u8)).is_err());
+    }
+
+    #[test]
+    fn cancellable_generator_test() {
+        let cancel = AtomicBool::new(false);
         assert_eq!(
-            prime_generator(20, Vec::new(), 0),
-            vec![2, 3, 5, 7, 11, 13, 17, 19]
+            cancellable_generator(200_000, Vec::new(), 0, &cancel),
+            Ok(prime_generator(200_000, Vec::new(), 0))
         );
         assert_eq!(
-            prime_generator(20, vec![2, 3, 5, 7], 0),
-            vec![2, 3, 5, 7, 11, 13, 17, 19]
+            cancellable_generator::<u8>(255, Vec::new(), 0, &cancel),
+            Ok(prime_generator(255, Vec::new(), 0))
         );
+
+        // Cancel as soon as a prime above 100000 is found.
+        let partial = cancellable_generator_map(
+            200_000,
+            Vec::new(),
+            0,
+            &cancel,
+            ReserveStrategy::Upfront,
+            |p| {
+                if p > 100_000 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            },
+        )
+        .unwrap_err();
+        assert!(partial.reached > 100_000 && partial.reached < 200_000);
         assert_eq!(
-            prime_generator(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], 0),
-            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+            partial.primes,
+            prime_generator(partial.reached, Vec::new(), 0)
+        );
+
+        // Continue from where it stopped.
+        cancel.store(false, Ordering::Relaxed);
+        assert_eq!(
+            cancellable_generator(200_000, partial.primes, partial.reached, &cancel),
+            Ok(prime_generator(200_000, Vec::new(), 0))
         );
     }
 
     #[test]
-    fn multi_generator_test() {
-        assert_eq!(par_prime_generator(0, Vec::new(), 0), Vec::new());
+    fn range_generator_test() {
         assert_eq!(
-            par_prime_generator(20, Vec::new(), 0),
+            primes_in_range(0, 20, Vec::new()),
             vec![2, 3, 5, 7, 11, 13, 17, 19]
         );
+        assert_eq!(primes_in_range(20, 20, Vec::new()), Vec::<u32>::new());
+        assert_eq!(primes_in_range(30, 20, Vec::new()), Vec::<u32>::new());
+        assert_eq!(primes_in_range(11, 13, Vec::new()), vec![11]);
         assert_eq!(
-            par_prime_generator(20, vec![2, 3, 5, 7], 0),
-            vec![2, 3, 5, 7, 11, 13, 17, 19]
+            primes_in_range(10, 20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]),
+            vec![11, 13, 17, 19]
         );
+
+        assert_eq!(primes_with_digits(0, Vec::<u32>::new()), Vec::<u32>::new());
+        assert_eq!(primes_with_digits(1, Vec::<u32>::new()), vec![2, 3, 5, 7]);
+        let two_digits = primes_with_digits(2, Vec::<u32>::new());
+        assert_eq!(two_digits.len(), 21);
+        assert_eq!((two_digits[0], two_digits[20]), (11, 97));
+        assert_eq!(primes_with_digits(3, Vec::<u32>::new()).len(), 143);
+        assert_eq!(primes_with_digits(4, Vec::<u32>::new()).len(), 1061);
+        // 10^d doesn't fit, so the primes go up to the maximum of the type.
+        assert_eq!(primes_with_digits(3, Vec::<u8>::new()).len(), 29);
+        assert_eq!(primes_with_digits(5, Vec::<u16>::new()).len(), 6542 - 1229);
         assert_eq!(
-            par_prime_generator(20, vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29], 0),
-            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+            primes_with_digits(3, Vec::<i8>::new()).last(),
+            Some(&i8::MAX)
+        );
+        // Not even 10^(d - 1) fits.
+        assert_eq!(primes_with_digits(4, Vec::<i8>::new()), Vec::<i8>::new());
+        assert_eq!(primes_with_digits(21, Vec::<u64>::new()), Vec::<u64>::new());
+        assert_eq!(
+            primes_with_digits(40, Vec::<u128>::new()),
+            Vec::<u128>::new()
         );
     }
 
     #[test]
-    fn par_chunks_generator_test() {
+    fn extend_primes_test() {
+        let mut primes = prime_generator(100u32, Vec::new(), 0);
+        let added = extend_primes(&mut primes, 10_000).to_vec();
+        let fresh = prime_generator(10_000, Vec::new(), 0);
+        assert_eq!(primes, fresh);
+        assert_eq!(added, fresh[25..]);
+
+        // The list doesn't have to reach the square root of until.
+        let mut primes = vec![2u32, 3, 5, 7];
+        extend_primes(&mut primes, 10_000);
+        assert_eq!(primes, fresh);
+
+        let mut primes = Vec::new();
+        extend_primes(&mut primes, 10_000);
+        assert_eq!(primes, fresh);
+
+        assert!(extend_primes(&mut primes, 5000).is_empty());
+        assert_eq!(primes, fresh);
+    }
+
+    #[test]
+    fn best_primes_below_test() {
+        assert_eq!(best_primes_below(0u32, Vec::new()), Vec::<u32>::new());
+        assert_eq!(best_primes_below(2u32, Vec::new()), Vec::<u32>::new());
+        assert_eq!(best_primes_below(3u32, Vec::new()), vec![2]);
+        assert_eq!(best_primes_below(1000u32, Vec::new()).len(), 168);
+        assert_eq!(best_primes_below(1_000_000u32, Vec::new()).len(), 78498);
+
+        // Every strategy gives the same result around the thresholds.
+        let expected = prime_generator(10_000u64, Vec::new(), 0);
+        for limit in [0, 999, 1000, 1001, usize::MAX] {
+            for until in [999, 1000, 1001, 10_000] {
+                let below: Vec<u64> = expected.iter().cloned().filter(|&p| p < until).collect();
+                assert_eq!(best_primes_below_with(until, Vec::new(), limit), below);
+                assert_eq!(
+                    best_primes_below_with(until, expected[..100].to_vec(), limit),
+                    below
+                );
+                assert_eq!(
+                    best_primes_below_with(until, expected[..1000].to_vec(), limit),
+                    below
+                );
+            }
+        }
+
+        // Any type that fits the sieve can use it.
         assert_eq!(
-            par_prime_generator_map_chunks(0, Vec::new(), 0, 10, |_, _| true, |_| {}),
-            Vec::new()
+            best_primes_below(1000u128, Vec::new()),
+            prime_generator(1000, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    #[ignore = "sieves up to 10^8 twice, run it with --release --ignored"]
+    fn best_primes_below_large_test() {
+        let until = 100_000_000u64;
+        let sieved = best_primes_below_with(until, Vec::new(), usize::MAX);
+        assert_eq!(sieved.len(), 5_761_455);
+        assert_eq!(sieved.last(), Some(&99_999_989));
+        assert_eq!(best_primes_below_with(until, Vec::new(), 0), sieved);
+
+        // Half of the primes are already known, so the list is continued.
+        let half = sieved.partition_point(|&p| p < until / 2);
+        let continued = best_primes_below_with(until, sieved[..half + 1].to_vec(), 0);
+        assert_eq!(continued, sieved);
+    }
+
+    #[test]
+    fn par_chunks_generator_from_test() {
+        let expected = prime_generator(1000u32, Vec::new(), 0);
+
+        // The calculation arrived to 300, and found some primes in the chunk after it.
+        let mut cache = prime_generator(300u32, Vec::new(), 0);
+        cache.extend([331, 337, 347]);
+        let mut starts = Vec::new();
+        let primes = par_prime_generator_map_chunks_from(
+            1000,
+            cache.clone(),
+            ChunkOptions::default().chunk_size(100).start_from(300),
+            |start, _| {
+                starts.push(start);
+                true
+            },
+            |_| {},
         );
+        assert_eq!(primes, expected);
+        assert_eq!(starts[0], 301);
+
+        // Starting from the last prime would skip the ones between 300 and 331.
+        let primes = par_prime_generator_map_chunks(1000, cache, 0, 100, |_, _| true, |_| {});
+        assert_ne!(primes, expected);
+
+        for resume_from in [0, 2, 3, 4, 10, 11, 999] {
+            let cache = prime_generator(1000u32, Vec::new(), 0);
+            let primes = par_prime_generator_map_chunks_from(
+                1000,
+                cache,
+                ChunkOptions::default()
+                    .chunk_size(7)
+                    .start_from(resume_from),
+                |_, _| true,
+                |_| {},
+            );
+            assert_eq!(primes, expected, "{}", resume_from);
+        }
+    }
+
+    #[test]
+    fn exclusive_bound_test() {
+        let expected: [&[u32]; 6] = [&[], &[], &[], &[2], &[2, 3], &[2, 3]];
+        for (until, expected) in expected.into_iter().enumerate() {
+            let until = until as u32;
+            assert_eq!(prime_generator(until, Vec::new(), 0), expected, "{}", until);
+            assert_eq!(
+                par_prime_generator(until, Vec::new(), 0),
+                expected,
+                "{}",
+                until
+            );
+            assert_eq!(
+                par_prime_generator_balanced(until, Vec::new(), 0, 1),
+                expected,
+                "{}",
+                until
+            );
+            assert_eq!(
+                par_prime_generator_map_chunks(until, Vec::new(), 0, 1, |_, _| true, |_| {}),
+                expected,
+                "{}",
+                until
+            );
+            assert_eq!(
+                cancellable_generator(until, Vec::new(), 0, &AtomicBool::new(false)),
+                Ok(expected.to_vec()),
+                "{}",
+                until
+            );
+            assert_eq!(best_primes_below(until, Vec::new()), expected, "{}", until);
+            assert_eq!(primes_in_range(0, until, Vec::new()), expected, "{}", until);
+        }
+        assert_eq!(gauss_function(0u32), 0);
+        assert_eq!(gauss_function(1u32), 0);
+        assert_eq!(gauss_function(2u32), 0);
+    }
+
+    #[test]
+    fn checked_gauss_function_test() {
+        for n in [0u64, 1, 2, 1000, 1 << 40, u64::MAX] {
+            assert_eq!(checked_gauss_function(n), Some(gauss_function(n)));
+        }
         assert_eq!(
-            par_prime_generator_map_chunks(0, vec![2, 3, 5], 0, 10, |_, _| true, |_| {}),
-            vec![2, 3, 5]
+            checked_gauss_function(u128::MAX),
+            Some(gauss_function(u128::MAX))
         );
+
+        // Too large for a finite f64.
+        let huge: num::BigUint = num::BigUint::from(1u8) << 2000;
+        assert_eq!(checked_gauss_function(huge.clone()), None);
+        assert_eq!(reservation_estimate(huge), None);
+        // Just above e^1.08366 the estimate is 201, which doesn't fit in an i8.
+        assert_eq!(checked_gauss_function(3u8), Some(201));
+        assert_eq!(checked_gauss_function(3i8), None);
+        assert_eq!(reservation_estimate(3i8), None);
+        // The estimate fits in the type, but not in a usize on 32-bit platforms.
         assert_eq!(
-            par_prime_generator_map_chunks(20, Vec::new(), 0, 10, |_, _| true, |_| {}),
-            vec![2, 3, 5, 7, 11, 13, 17, 19]
+            reservation_estimate(u64::MAX),
+            gauss_function(u64::MAX).to_usize()
         );
+    }
+
+    #[test]
+    fn const_primes_test() {
+        const FIRST_TEN: [u32; 10] = const_primes();
+        assert_eq!(FIRST_TEN, [2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        const NONE: [u32; 0] = const_primes();
+        assert_eq!(NONE, [0u32; 0]);
+        const THOUSAND: [u32; 1000] = const_primes();
+        assert_eq!(THOUSAND[..], prime_generator(10_000, Vec::new(), 0)[..1000]);
+    }
+
+    #[test]
+    fn inverse_generator_test() {
+        let (primes, ranks) = prime_generator_with_inverse(100u32, Vec::new());
+        assert_eq!(primes, prime_generator(100, Vec::new(), 0));
+        assert_eq!(ranks.len(), 25);
+        for (i, &p) in primes.iter().enumerate() {
+            assert_eq!(ranks[&p], i + 1);
+            assert_eq!(crate::calculator::prime_index(p, &primes), Some(i + 1));
+        }
+        assert!((0..100)
+            .filter(|n| !primes.contains(n))
+            .all(|n| !ranks.contains_key(&n)));
+
+        let (primes_from, ranks_from) = prime_generator_with_inverse(100u32, primes[..10].to_vec());
+        assert_eq!((primes_from, ranks_from), (primes, ranks));
+    }
+
+    #[test]
+    fn bucketed_generator_test() {
+        let expected = prime_generator(10_000u32, Vec::new(), 0);
+        for num_buckets in [1, 2, 6, 7, 100, 20_000] {
+            let buckets = prime_generator_bucketed(10_000u32, num_buckets).unwrap();
+            assert_eq!(buckets.len(), num_buckets);
+            for (i, bucket) in buckets.iter().enumerate() {
+                assert!(bucket.iter().all(|&p| p as usize % num_buckets == i));
+            }
+            let mut primes = buckets.concat();
+            primes.sort();
+            assert_eq!(primes, expected);
+        }
+        // 300 doesn't fit in a u8.
+        let buckets = prime_generator_bucketed(100u8, 300).unwrap();
+        assert_eq!(buckets[97], vec![97]);
+        assert_eq!(prime_generator_bucketed(10_000u32, 0), Err(NoBuckets));
+    }
+
+    #[test]
+    fn prime_generator_timed_test() {
+        let budget = Duration::from_millis(50);
+        let start = Instant::now();
+        let primes = prime_generator_timed(start + budget, 0u64);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= budget);
+        assert!(
+            elapsed < budget + Duration::from_millis(500),
+            "{:?}",
+            elapsed
+        );
+        let last = *primes.last().unwrap();
+        assert!(last > 1000);
+        assert_eq!(primes, prime_generator(last + 1, Vec::new(), 0));
+
+        let primes = prime_generator_timed(Instant::now() + budget, 1_000_000u64);
+        let expected = prime_generator(primes.last().unwrap() + 1, Vec::new(), 0);
         assert_eq!(
-            par_prime_generator_map_chunks(20, vec![2, 3, 5, 7], 0, 10, |_, _| true, |_| {}),
-            vec![2, 3, 5, 7, 11, 13, 17, 19]
+            primes,
+            expected[expected.partition_point(|&p| p < 1_000_000)..]
         );
+
+        // The calculation ends at the maximum value of the type.
+        let primes = prime_generator_timed(Instant::now() + Duration::from_secs(60), 0u8);
+        assert_eq!(primes, prime_generator(255, Vec::new(), 0));
+        let primes = prime_generator_timed(Instant::now() + Duration::from_secs(60), 250u8);
+        assert_eq!(primes, vec![251]);
+
+        assert!(prime_generator_timed(Instant::now(), 0u32).is_empty());
+    }
+
+    #[test]
+    fn reserve_strategy_test() {
+        for until in [0u32, 3, 4, 100, 65_536, 65_537, 1_000_000] {
+            let upfront = prime_generator_reserving(until, Vec::new(), 0, ReserveStrategy::Upfront);
+            let geometric =
+                prime_generator_reserving(until, Vec::new(), 0, ReserveStrategy::Geometric);
+            assert_eq!(upfront, geometric, "{}", until);
+            assert_eq!(upfront, prime_generator(until, Vec::new(), 0));
+            assert!(geometric.capacity() <= cmp::max(gauss_function(until) as usize, 2));
+
+            let cancel = AtomicBool::new(false);
+            for reserve in [ReserveStrategy::Upfront, ReserveStrategy::Geometric] {
+                assert_eq!(
+                    cancellable_generator_reserving(until, Vec::new(), 0, &cancel, reserve),
+                    Ok(upfront.clone())
+                );
+            }
+        }
+
+        // Cancelled during the first chunk of 2^16 numbers, so the flag is seen right after it.
+        let until = 1u32 << 28;
+        let cancel = AtomicBool::new(false);
+        let cancelled = |reserve| {
+            let mut found = 0;
+            let result = cancellable_generator_map(until, Vec::new(), 0, &cancel, reserve, |_| {
+                found += 1;
+                if found == 100 {
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            });
+            cancel.store(false, Ordering::Relaxed);
+            result.unwrap_err()
+        };
+        let upfront = cancelled(ReserveStrategy::Upfront);
+        let geometric = cancelled(ReserveStrategy::Geometric);
+        assert_eq!(upfront, geometric);
         assert_eq!(
-            par_prime_generator_map_chunks(
-                20,
-                vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29],
-                0,
-                10,
-                |_, _| true,
+            upfront.primes,
+            prime_generator(upfront.reached, Vec::new(), 0)
+        );
+        assert!(upfront.primes.capacity() >= gauss_function(until) as usize);
+        assert!(geometric.primes.capacity() < 2 * geometric.primes.len());
+    }
+
+    #[test]
+    fn chunks_controlled_test() {
+        let continue_all = |_, _| ControlFlow::Continue(());
+        for until in [0u32, 3, 20, 1000] {
+            assert_eq!(
+                par_prime_generator_map_chunks_controlled(
+                    until,
+                    Vec::new(),
+                    ChunkOptions::default().chunk_size(7),
+                    continue_all,
+                    |_| {}
+                ),
+                (prime_generator(until, Vec::new(), 0), None)
+            );
+        }
+
+        // The reason is returned whether the first chunk or a later one is refused.
+        for (stop_at, expected) in [(0, vec![]), (20, vec![2, 3, 5, 7, 11, 13, 17, 19])] {
+            let cancel = AtomicBool::new(false);
+            let (primes, reason) = par_prime_generator_map_chunks_controlled(
+                1000u32,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(10),
+                |start, _| {
+                    if start >= stop_at {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        ControlFlow::Break(StopReason::Cancelled)
+                    } else {
+                        ControlFlow::Continue(())
+                    }
+                },
                 |_| {},
+            );
+            assert_eq!(primes, expected);
+            assert_eq!(reason, Some(StopReason::Cancelled));
+        }
+
+        let mut chunks = 0;
+        let (primes, reason) = par_prime_generator_map_chunks_controlled(
+            1000u32,
+            Vec::new(),
+            ChunkOptions::default().chunk_size(100),
+            |_, _| {
+                chunks += 1;
+                if chunks > 3 {
+                    ControlFlow::Break(StopReason::Budget)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+            |_| {},
+        );
+        assert_eq!(primes, prime_generator(300, Vec::new(), 0));
+        assert_eq!(reason, Some(StopReason::Budget));
+        assert_eq!(reason.unwrap().to_string(), "the budget ran out");
+    }
+
+    #[test]
+    fn nth_primes_test() {
+        let primes = prime_generator(200_000u64, Vec::new(), 0);
+        assert_eq!(nth_primes(&[1, 6, 10001]), vec![2, 13, 104743]);
+        assert_eq!(nth_primes(&[]), Vec::<u64>::new());
+
+        let indices: Vec<usize> = (1..=primes.len()).rev().step_by(997).collect();
+        let expected: Vec<u64> = indices.iter().map(|&i| primes[i - 1]).collect();
+        assert_eq!(nth_primes(&indices), expected);
+
+        // The bound is above the nth prime for every n.
+        for (i, &p) in primes.iter().enumerate() {
+            assert!(nth_prime_bound(i + 1) > p, "{}", i + 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn nth_primes_panic() {
+        nth_primes(&[1, 0]);
+    }
+
+    #[test]
+    fn overflow_mode_test() {
+        let expected = prime_generator(u16::MAX, Vec::new(), 0);
+        for chunk_size in [1000u16, 65_535] {
+            assert_eq!(
+                par_prime_generator_chunked(
+                    u16::MAX,
+                    Vec::new(),
+                    ChunkOptions::default().chunk_size(chunk_size),
+                    OverflowMode::Saturating
+                ),
+                Ok(expected.clone())
+            );
+        }
+        // 65 chunks fit before 65000, and 65000 + 1000 overflows.
+        let partial = par_prime_generator_chunked(
+            u16::MAX,
+            Vec::new(),
+            ChunkOptions::default().chunk_size(1000),
+            OverflowMode::Checked,
+        )
+        .unwrap_err();
+        assert_eq!(partial.reached, 65_000);
+        assert_eq!(partial.primes, prime_generator(65_000, Vec::new(), 0));
+        // Nothing overflows if the chunks fit.
+        assert_eq!(
+            par_prime_generator_chunked(
+                u16::MAX,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(65_535),
+                OverflowMode::Checked
             ),
-            vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]
+            Ok(expected.clone())
+        );
+        assert_eq!(
+            par_prime_generator_chunked(
+                u16::MAX,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(1000),
+                OverflowMode::Checked
+            )
+            .unwrap_err()
+            .to_string(),
+            "the end of a chunk overflows at 65000"
+        );
+        assert_eq!(
+            cancellable_generator(20, vec![2, 3, 5, 7], 0, &AtomicBool::new(true))
+                .unwrap_err()
+                .to_string(),
+            "the calculation was cancelled at 9"
+        );
+
+        let panicked = std::panic::catch_unwind(|| {
+            par_prime_generator_chunked(
+                u16::MAX,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(1000),
+                OverflowMode::Panic,
+            )
+        });
+        assert!(panicked.is_err());
+        assert_eq!(
+            par_prime_generator_chunked(
+                60_000u16,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(1000),
+                OverflowMode::Panic
+            ),
+            Ok(prime_generator(60_000, Vec::new(), 0))
+        );
+    }
+
+    #[test]
+    fn boxed_primes_test() {
+        assert_eq!(boxed_primes(Some(20u32)).count(), 8);
+        assert_eq!(
+            boxed_primes::<u64>(None).take(5).collect::<Vec<_>>(),
+            vec![2, 3, 5, 7, 11]
+        );
+        for until in [0u32, 2, 3, 100_000] {
+            assert_eq!(
+                boxed_primes(Some(until)).collect::<Vec<_>>(),
+                prime_generator(until, Vec::new(), 0)
+            );
+        }
+        // The unbounded iterator ends at the maximum value of the type.
+        assert_eq!(
+            boxed_primes::<u8>(None).collect::<Vec<_>>(),
+            prime_generator(u8::MAX, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn prime_generator_verified_test() {
+        for until in [0u32, 2, 3, 20, 100_000, 1_000_000] {
+            assert_eq!(
+                prime_generator_verified(until),
+                Ok(prime_generator(until, Vec::new(), 0))
+            );
+        }
+    }
+
+    #[test]
+    fn sum_of_first_primes_test() {
+        assert_eq!(sum_of_first_primes(0), 0);
+        assert_eq!(sum_of_first_primes(1), 2);
+        assert_eq!(sum_of_first_primes(10), 129);
+        let primes = prime_generator(1_000_000u64, Vec::new(), 0);
+        for n in [2, 5, 6, 7, 100, 1000, primes.len()] {
+            let expected: u64 = primes[..n].iter().sum();
+            assert_eq!(sum_of_first_primes(n), expected, "{}", n);
+            assert_eq!(checked_sum_of_first_primes(n), Some(expected));
+            assert_eq!(sum_of_first_primes_u128(n), expected as u128);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn primes_as_array_test() {
+        for until in [0, 2, 3, 1000, 100_000] {
+            let primes = prime_generator(until, Vec::new(), 0);
+            let array = primes_as_array(until);
+            assert_eq!(array.len(), primes.len());
+            assert_eq!(array.to_vec(), primes);
+        }
+    }
+
+    #[test]
+    fn prime_generator_tail_test() {
+        assert_eq!(prime_generator_tail(100u32, 3), vec![83, 89, 97]);
+        assert_eq!(prime_generator_tail(100u32, 0), Vec::<u32>::new());
+        for until in 0..200u32 {
+            let primes = prime_generator(until, Vec::new(), 0);
+            for k in [1, 2, 5, 100] {
+                let tail = primes[primes.len().saturating_sub(k)..].to_vec();
+                assert_eq!(prime_generator_tail(until, k), tail, "{} {}", until, k);
+            }
+        }
+
+        let primes = prime_generator(1_000_000u64, Vec::new(), 0);
+        assert_eq!(
+            prime_generator_tail(1_000_000u64, 10),
+            primes[primes.len() - 10..]
         );
     }
 
+    #[test]
+    fn popcount_test() {
+        let primes = primes_with_popcount(1000u32);
+        assert_eq!(primes.len(), 168);
+        assert!(primes.contains(&(7, 3)));
+        assert!(primes.iter().all(|&(p, weight)| p.count_ones() == weight));
+
+        assert_eq!(primes_with_exact_popcount(10u32, 2), vec![3, 5]);
+        assert_eq!(primes_with_exact_popcount(100_000u32, 1), vec![2]);
+        assert_eq!(
+            primes_with_exact_popcount(100_000u32, 2),
+            vec![3, 5, 17, 257, 65537]
+        );
+        assert_eq!(primes_with_exact_popcount(100u32, 0), Vec::<u32>::new());
+        // Mersenne primes below 2^20.
+        assert_eq!(
+            primes_with_popcount(1u32 << 20)
+                .into_iter()
+                .filter_map(|(p, weight)| (p == (1 << weight) - 1).then_some(p))
+                .collect::<Vec<_>>(),
+            vec![3, 7, 31, 127, 8191, 131071, 524287]
+        );
+    }
+
+    #[test]
+    fn mark_primes_test() {
+        let mut flags = [true; 10];
+        mark_primes(90, &mut flags, &[2u32, 3, 5, 7]);
+        let marked: Vec<usize> = (0..10).filter(|&i| flags[i]).collect();
+        assert_eq!(marked, vec![7]);
+
+        let primes = prime_generator(100_000u64, Vec::new(), 0);
+        let known_primes = prime_generator(1000u64, Vec::new(), 0);
+        for start in [0, 1, 2, 57, 99_000] {
+            let mut flags = vec![true; 1000];
+            let mut par_flags = vec![false; 1000];
+            mark_primes(start, &mut flags, &known_primes);
+            par_mark_primes(start, &mut par_flags, &known_primes);
+            assert_eq!(flags, par_flags);
+            for (i, &flag) in flags.iter().enumerate() {
+                assert_eq!(flag, primes.binary_search(&((start + i) as u64)).is_ok());
+            }
+        }
+
+        mark_primes::<u32>(5, &mut [], &[]);
+    }
+
+    #[test]
+    fn primes_after_gap_test() {
+        assert_eq!(
+            primes_after_gap(100u32, 6, Vec::new()),
+            vec![29, 37, 53, 59, 67, 79, 89, 97]
+        );
+        // The list is longer than until.
+        assert_eq!(
+            primes_after_gap(90u32, 6, prime_generator(200, Vec::new(), 0)),
+            vec![29, 37, 53, 59, 67, 79, 89]
+        );
+        assert_eq!(primes_after_gap(3u32, 0, Vec::new()), Vec::<u32>::new());
+        assert_eq!(primes_after_gap(4u32, 0, Vec::new()), vec![3]);
+        assert_eq!(primes_after_gap(100u32, 0, Vec::new()).len(), 24);
+
+        // The first gap of at least 72 goes from 31397 to 31469.
+        let primes = primes_after_gap(100_000u32, 72, Vec::new());
+        assert_eq!(primes[0], 31469);
+    }
+
+    #[test]
+    fn prime_pi_generate_test() {
+        for (n, pi) in [(0u64, 0), (3, 1), (4, 2), (1000, 168), (1_000_000, 78498)] {
+            assert_eq!(prime_pi_generate(n), pi);
+        }
+    }
+
+    #[test]
+    fn nth_prime_test() {
+        let primes = prime_generator(200_000u32, Vec::new(), 0);
+        for n in (1..100).chain([1000, 10_000, primes.len()]) {
+            assert_eq!(nth_prime::<u32>(n), primes[n - 1], "{}", n);
+        }
+        // The bound doesn't fit, or the prime is the maximum value.
+        assert_eq!(nth_prime::<u8>(54), 251);
+        assert_eq!(nth_prime::<i8>(31), 127);
+        assert_eq!(nth_prime::<u64>(100_000), 1_299_709);
+    }
+
     #[test]
     #[should_panic]
-    fn par_chunks_panic() {
-        par_prime_generator_map_chunks(20, Vec::new(), 0, 0, |_, _| true, |_| {});
+    fn nth_prime_zero() {
+        nth_prime::<u32>(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nth_prime_overflow() {
+        nth_prime::<u8>(55);
+    }
+
+    #[test]
+    fn next_prev_prime_test() {
+        let primes = prime_generator(10_000u32, Vec::new(), 0);
+        for n in 0..9973u32 {
+            let next = primes[primes.partition_point(|&p| p <= n)];
+            assert_eq!(next_prime(n, &primes), next, "{}", n);
+            assert_eq!(next_prime(n, &[]), next, "{}", n);
+
+            let prev = primes[..primes.partition_point(|&p| p < n)].last().copied();
+            assert_eq!(prev_prime(n, &primes), prev, "{}", n);
+            assert_eq!(prev_prime(n, &[2, 3]), prev, "{}", n);
+        }
+
+        // Just below and at a prime.
+        assert_eq!(next_prime(1_000_002u64, &[]), 1_000_003);
+        assert_eq!(next_prime(1_000_003u64, &[]), 1_000_033);
+        assert_eq!(prev_prime(1_000_004u64, &[]), Some(1_000_003));
+        assert_eq!(prev_prime(1_000_003u64, &[]), Some(999_983));
+
+        assert_eq!(next_prime(-5i32, &[]), 2);
+        assert_eq!(prev_prime(-5i32, &[]), None);
+        assert_eq!(next_prime(250u8, &[]), 251);
+        assert_eq!(prev_prime(u8::MAX, &[]), Some(251));
+
+        assert_eq!(checked_next_prime(250u8, &[]), Some(251));
+        assert_eq!(checked_next_prime(251u8, &[]), None);
+        assert_eq!(checked_next_prime(18_446_744_073_709_551_557u64, &[]), None);
+        assert_eq!(prev_prime(u64::MAX, &[]), Some(18_446_744_073_709_551_557));
+    }
+
+    #[test]
+    #[should_panic]
+    fn next_prime_overflow() {
+        next_prime(251u8, &[]);
+    }
+
+    #[test]
+    fn sieve_generator_test() {
+        for until in (0..300u32).chain([65_536, 65_537, 100_000]) {
+            assert_eq!(
+                sieve_generator(until),
+                prime_generator(until, Vec::new(), 0),
+                "{}",
+                until
+            );
+        }
+        assert_eq!(sieve_generator(-10i32), Vec::<i32>::new());
+        assert_eq!(sieve_generator(u8::MAX).last(), Some(&251));
+        // A window is 2 * 8 * DEFAULT_SEGMENT_BYTES numbers: the primes around its end.
+        let window = 16 * DEFAULT_SEGMENT_BYTES as u64;
+        let primes = sieve_generator(window + 100);
+        for n in window - 100..window + 100 {
+            assert_eq!(primes.binary_search(&n).is_ok(), deterministic_is_prime(n));
+        }
+    }
+
+    #[test]
+    fn wheel_generator_test() {
+        for until in (0..500u32).chain([65_536, 100_000]) {
+            assert_eq!(
+                wheel_generator(until, Vec::new(), 0),
+                prime_generator(until, Vec::new(), 0),
+                "{}",
+                until
+            );
+        }
+        // Continuing a list, or starting from an odd number.
+        let primes = prime_generator(10_000u32, Vec::new(), 0);
+        for (len, start_from) in [
+            (1, 0),
+            (2, 0),
+            (2, 3),
+            (2, 5),
+            (3, 0),
+            (3, 7),
+            (10, 0),
+            (10, 31),
+            (26, 1001),
+        ] {
+            assert_eq!(
+                wheel_generator(10_000, primes[..len].to_vec(), start_from),
+                prime_generator(10_000, primes[..len].to_vec(), start_from),
+                "{} {}",
+                len,
+                start_from
+            );
+        }
+        assert_eq!(wheel_generator(20, primes.clone(), 0), primes);
+
+        // Until near the maximum value.
+        assert_eq!(
+            wheel_generator(u8::MAX, Vec::new(), 0),
+            prime_generator(u8::MAX, Vec::new(), 0)
+        );
+        assert_eq!(
+            wheel_generator(i8::MAX, Vec::new(), 0),
+            prime_generator(i8::MAX, Vec::new(), 0)
+        );
+    }
+
+    #[test]
+    fn par_cancellable_test() {
+        let expected = prime_generator(100_000u32, Vec::new(), 0);
+        let go = AtomicBool::new(false);
+        assert_eq!(
+            par_prime_generator_cancellable(
+                100_000,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(7_000),
+                &go
+            ),
+            Ok(expected.clone())
+        );
+
+        // Cancelled from another thread at some point: what was found is still valid, and can be resumed.
+        let cancel = AtomicBool::new(false);
+        let result = std::thread::scope(|s| {
+            s.spawn(|| cancel.store(true, Ordering::Relaxed));
+            par_prime_generator_cancellable(
+                100_000,
+                Vec::new(),
+                ChunkOptions::default().chunk_size(1_000),
+                &cancel,
+            )
+        });
+        if let Err(partial) = result {
+            assert_eq!(partial.reason, StopReason::Cancelled);
+            assert_eq!(
+                partial.primes[..],
+                expected[..expected.partition_point(|&p| p < partial.reached)]
+            );
+            assert_eq!(
+                par_prime_generator_cancellable(
+                    100_000,
+                    partial.primes,
+                    ChunkOptions::default()
+                        .chunk_size(1_000)
+                        .start_from(partial.reached),
+                    &go
+                ),
+                Ok(expected.clone())
+            );
+        }
+
+        // It stops where the sequential version does, and 2 and 3 aren't added.
+        let stop = AtomicBool::new(true);
+        for known in [&[][..], &[2], &expected[..10]] {
+            assert_eq!(
+                par_prime_generator_cancellable(
+                    100_000,
+                    known.to_vec(),
+                    ChunkOptions::default().chunk_size(1_000),
+                    &stop
+                ),
+                cancellable_generator(100_000, known.to_vec(), 0, &stop),
+                "{:?}",
+                known
+            );
+        }
+    }
+
+    #[test]
+    fn chunk_options_test() {
+        let options = ChunkOptions::<u32>::default();
+        assert_eq!(options.start_from, 0);
+        assert_eq!(options.chunk_size, DEFAULT_CHUNK_SIZE);
+        assert_eq!(ChunkOptions::<u16>::default().chunk_size, u16::MAX);
+        assert_eq!(ChunkOptions::<i8>::default().chunk_size, i8::MAX);
+
+        let expected = prime_generator(100_000u32, Vec::new(), 0);
+        assert_eq!(
+            par_prime_generator_map_chunks_with(100_000, Vec::new(), options, |_, _| true, |_| {}),
+            expected
+        );
+        let options = options.chunk_size(7_000).start_from(50_001);
+        assert_eq!(
+            par_prime_generator_map_chunks_with(100_000, Vec::new(), options, |_, _| true, |_| {}),
+            par_prime_generator_map_chunks(100_000, Vec::new(), 50_001, 7_000, |_, _| true, |_| {})
+        );
+    }
+
+    #[test]
+    fn inclusive_test() {
+        let expected = prime_generator(10_001u32, Vec::new(), 0);
+        for until in [0, 1, 2, 3, 4, 9_973, 9_974, 10_000] {
+            let below = expected.partition_point(|&p| p <= until);
+            assert_eq!(
+                prime_generator_inclusive(until, Vec::new(), 0)[..],
+                expected[..below],
+                "{}",
+                until
+            );
+            assert_eq!(
+                par_prime_generator_inclusive(until, Vec::new(), 0)[..],
+                expected[..below],
+                "{}",
+                until
+            );
+        }
+
+        // until is already in the list.
+        assert_eq!(
+            prime_generator_inclusive(7, vec![2, 3, 5, 7, 11], 0),
+            vec![2, 3, 5, 7, 11]
+        );
+
+        // The maximum values don't overflow.
+        assert_eq!(
+            prime_generator_inclusive(i8::MAX, Vec::new(), 0).last(),
+            Some(&127)
+        );
+        assert_eq!(
+            prime_generator_inclusive(u8::MAX, Vec::new(), 0).last(),
+            Some(&251)
+        );
+        assert_eq!(
+            par_prime_generator_inclusive(u16::MAX, Vec::new(), 0).last(),
+            Some(&65_521)
+        );
     }
 }
@@ -0,0 +1,763 @@
+use crate::format::*;
+use crate::generator::*;
+#[cfg(feature = "parquet")]
+use arrow_array::{Array, ArrayRef, RecordBatch, UInt64Array};
+#[cfg(feature = "parquet")]
+use arrow_schema::{DataType, Field, Schema};
+use num::integer::Roots;
+use num::range_step;
+use num::{CheckedAdd, FromPrimitive, ToPrimitive};
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+use std::cmp;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::iter;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+/// Parallelely calculates every prime less than until and writes them to a file in the format of [`crate::format`].
+///
+/// Only the primes up to the square root of until are kept in memory: the rest are calculated one chunk at a time, and every chunk is written to the file before moving to the next one.
+///
+/// The number the file says the calculation arrived to is until.
+///
+/// # Panics
+///
+/// The function panics if chunk_size is 0.
+pub fn par_generate_to_file<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + Element + ParCandidates,
+    P: AsRef<Path>,
+>(
+    until: N,
+    path: P,
+    chunk_size: N,
+) -> io::Result<()> {
+    assert!(!chunk_size.is_zero());
+
+    let three = N::one() + N::one() + N::one();
+    let base_until = cmp::max(until.sqrt() + N::one(), three);
+    let base_primes = prime_generator(base_until, Vec::new(), N::zero());
+
+    let mut file = BufWriter::new(File::create(path)?);
+    write_header::<N, _>(&mut file)?;
+
+    let below_until = base_primes.partition_point(|&p| p < until);
+    write_elements(&mut file, &base_primes[..below_until])?;
+
+    for start in range_step(base_until, until, chunk_size) {
+        let end = OverflowMode::Saturating
+            .chunk_end(start, chunk_size, until)
+            .unwrap();
+        let chunk = par_find_primes(end, &base_primes, start, usize::MAX, |_| {});
+        write_elements(&mut file, &chunk)?;
+    }
+
+    write_elements(&mut file, &[until])?;
+    file.flush()
+}
+
+/// How many numbers [`primes_reversed`] reads from the file at a time.
+const REVERSED_BLOCK_LEN: usize = 4096;
+
+/// Opens a prime file, returning it with whether its numbers need their bytes swapped and how many primes it contains.
+fn open_prime_file<N: Element>(path: &Path) -> io::Result<(File, bool, usize)> {
+    let mut file = File::open(path)?;
+    let (swap, count) = inspect_prime_file::<N>(&mut file)?;
+    Ok((file, swap, count))
+}
+
+/// Reads the header of a prime file, returning whether its numbers need their bytes swapped and how many primes it contains.
+fn inspect_prime_file<N: Element>(file: &mut File) -> io::Result<(bool, usize)> {
+    file.seek(SeekFrom::Start(0))?;
+    let swap = read_header::<N, _>(file)?;
+    let len = file.metadata()?.len() as usize - HEADER_LEN;
+    if !len.is_multiple_of(N::WIDTH) {
+        return Err(invalid_data("the file ends in the middle of a number"));
+    }
+    // The last number is the one the calculation arrived to.
+    let count = (len / N::WIDTH)
+        .checked_sub(1)
+        .ok_or_else(|| invalid_data("the file doesn't say where the calculation arrived to"))?;
+    Ok((swap, count))
+}
+
+/// Reads len numbers from the file, starting from the one at the index.
+fn read_block<N: Element>(
+    file: &mut File,
+    swap: bool,
+    index: usize,
+    len: usize,
+) -> io::Result<Vec<N>> {
+    file.seek(SeekFrom::Start(element_offset::<N>(index) as u64))?;
+    read_elements(&mut file.take((len * N::WIDTH) as u64), swap)
+}
+
+/// Reads the primes of a file in the format of [`crate::format`] from the last to the first, without loading the whole file in memory.
+///
+/// If the file can't be opened or its header is invalid, the error is the only item. The number the calculation arrived to isn't returned.
+///
+/// # Examples
+///
+/// ```
+/// use prime::format::*;
+/// use prime::io::*;
+///
+/// let path = std::env::temp_dir().join(format!("prime_primes_reversed_example_{}.dat", std::process::id()));
+/// write_primes(&mut std::fs::File::create(&path).unwrap(), &[2u32, 3, 5, 7], 10).unwrap();
+///
+/// let primes: Vec<u32> = primes_reversed(&path).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(primes, vec![7, 5, 3, 2]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn primes_reversed<N: Element, P: AsRef<Path>>(path: P) -> impl Iterator<Item = io::Result<N>> {
+    let mut opened = Some(open_prime_file::<N>(path.as_ref()));
+    let mut state = None;
+    let mut block = Vec::new();
+    iter::from_fn(move || {
+        if let Some(opened) = opened.take() {
+            match opened {
+                Ok(s) => state = Some(s),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if let Some(n) = block.pop() {
+            return Some(Ok(n));
+        }
+
+        let (file, swap, remaining) = state.as_mut()?;
+        if *remaining == 0 {
+            return None;
+        }
+        let len = cmp::min(*remaining, REVERSED_BLOCK_LEN);
+        *remaining -= len;
+        match read_block(file, *swap, *remaining, len) {
+            Ok(b) => block = b,
+            Err(e) => {
+                state = None;
+                return Some(Err(e));
+            }
+        }
+        block.pop().map(Ok)
+    })
+}
+
+/// Calculates every prime less than until and writes them to a file in the format of [`crate::format`], resuming the calculation the file contains if it exists.
+///
+/// The primes are calculated in parallel one chunk at a time. After every chunk, the file is updated to say where the calculation arrived to, so it's always valid: it can be resumed even if the process is interrupted between two chunks.
+///
+/// Before every chunk, the calculation stops if `cancel` is true. The number the calculation arrived to is returned: if it's until, the file is complete.
+///
+/// Like [`par_generate_to_file`], only the primes up to the square root of until are kept in memory: only those are read from the file, and every new chunk is appended to it and then dropped. The file holds the primes and the number they were computed up to, like a [`crate::cache::PrimeCache`].
+///
+/// Returns an error of kind `InvalidData` if the file exists but isn't a prime file with numbers of type N.
+///
+/// # Examples
+///
+/// ```
+/// use prime::format::*;
+/// use prime::io::*;
+/// use std::sync::atomic::AtomicBool;
+///
+/// let path = std::env::temp_dir().join(format!("prime_resumable_example_{}.dat", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let cancel = AtomicBool::new(false);
+/// assert_eq!(generate_to_file_resumable(&path, 10u32, 4, &cancel).unwrap(), 10);
+/// assert_eq!(generate_to_file_resumable(&path, 20u32, 4, &cancel).unwrap(), 20);
+///
+/// let (primes, arrived_to) = read_primes::<u32, _>(&mut std::fs::File::open(&path).unwrap()).unwrap();
+/// assert_eq!((primes, arrived_to), (vec![2, 3, 5, 7, 11, 13, 17, 19], 20));
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// # Panics
+///
+/// The function panics if chunk_size is 0.
+pub fn generate_to_file_resumable<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + Element + ParCandidates,
+    P: AsRef<Path>,
+>(
+    path: P,
+    until: N,
+    chunk_size: N,
+    cancel: &AtomicBool,
+) -> io::Result<N> {
+    assert!(!chunk_size.is_zero());
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)?;
+    if file.metadata()?.len() == 0 {
+        write_primes(&mut file, &[], N::zero())?;
+    }
+    let (swap, count) = inspect_prime_file::<N>(&mut file)?;
+    let mut arrived_to = read_block(&mut file, swap, count, 1)?[0];
+    if arrived_to >= until {
+        return Ok(arrived_to);
+    }
+
+    let three = N::one() + N::one() + N::one();
+    let base_until = cmp::max(until.sqrt() + N::one(), three);
+    let base_primes = if arrived_to >= base_until {
+        // Less than half of the numbers below base_until are prime.
+        let len = cmp::min(count, base_until.to_usize().unwrap_or(usize::MAX) / 2 + 1);
+        let mut base_primes = read_block::<N>(&mut file, swap, 0, len)?;
+        base_primes.truncate(base_primes.partition_point(|&p| p < base_until));
+        base_primes
+    } else {
+        prime_generator(base_until, Vec::new(), N::zero())
+    };
+
+    // Replace the number the calculation arrived to with the new primes, followed by the new one.
+    let mut append = |new_primes: &[N], reached: N| {
+        file.seek(SeekFrom::End(-(N::WIDTH as i64)))?;
+        write_elements(&mut file, new_primes)?;
+        write_elements(&mut file, &[reached])
+    };
+
+    if arrived_to < base_until {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(arrived_to);
+        }
+        let reached = cmp::min(base_until, until);
+        let from = base_primes.partition_point(|&p| p < arrived_to);
+        let to = base_primes.partition_point(|&p| p < reached);
+        append(&base_primes[from..to], reached)?;
+        arrived_to = reached;
+    }
+    for start in range_step(arrived_to, until, chunk_size) {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let end = OverflowMode::Saturating
+            .chunk_end(start, chunk_size, until)
+            .unwrap();
+        let chunk = par_find_primes(end, &base_primes, start, usize::MAX, |_| {});
+        append(&chunk, end)?;
+        arrived_to = end;
+    }
+    Ok(arrived_to)
+}
+
+/// The length in bytes of the prefix of the records yielded by [`encoded_stream`], which holds the length of the rest of the record as a little-endian u64.
+pub const RECORD_PREFIX_LEN: usize = 8;
+
+/// Frames the primes in a record: the length prefix followed by a complete prime file.
+fn encode_record<N: Element>(primes: &[N], arrived_to: N) -> Vec<u8> {
+    let mut record = vec![0; RECORD_PREFIX_LEN];
+    write_primes(&mut record, primes, arrived_to).unwrap();
+    let len = (record.len() - RECORD_PREFIX_LEN) as u64;
+    record[..RECORD_PREFIX_LEN].copy_from_slice(&len.to_le_bytes());
+    record
+}
+
+/// Calculates every prime less than until one chunk at a time, yielding every chunk already serialized, ready to be written to a socket or a file.
+///
+/// Every record is self-contained: [`RECORD_PREFIX_LEN`] bytes with the length of the rest, followed by a complete prime file in the format of [`crate::format`] with the primes of the chunk, which says the calculation arrived to the end of the chunk.
+/// The records can be read back one by one with [`read_record`].
+///
+/// Only the primes up to the square root of until are kept in memory, and every chunk is calculated in parallel when it's requested.
+///
+/// # Examples
+///
+/// ```
+/// use prime::io::*;
+///
+/// let bytes: Vec<u8> = encoded_stream(20u32, 10).flatten().collect();
+/// let mut reader = bytes.as_slice();
+/// assert_eq!(read_record::<u32, _>(&mut reader).unwrap(), Some((vec![2, 3, 5, 7], 10)));
+/// assert_eq!(read_record::<u32, _>(&mut reader).unwrap(), Some((vec![11, 13, 17, 19], 20)));
+/// assert_eq!(read_record::<u32, _>(&mut reader).unwrap(), None);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if chunk_size is 0.
+pub fn encoded_stream<
+    N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + Sync + Element + ParCandidates,
+>(
+    until: N,
+    chunk_size: N,
+) -> impl Iterator<Item = Vec<u8>> {
+    assert!(!chunk_size.is_zero());
+
+    let three = N::one() + N::one() + N::one();
+    let base_until = cmp::max(until.sqrt() + N::one(), three);
+    let base_primes = prime_generator(base_until, Vec::new(), N::zero());
+
+    range_step(N::zero(), until, chunk_size).map(move |start| {
+        let end = OverflowMode::Saturating
+            .chunk_end(start, chunk_size, until)
+            .unwrap();
+        let from = base_primes.partition_point(|&p| p < start);
+        let to = base_primes.partition_point(|&p| p < end);
+        let mut primes = base_primes[from..to].to_vec();
+        let candidates_start = cmp::max(start, base_until);
+        if candidates_start < end {
+            primes.append(&mut par_find_primes(
+                end,
+                &base_primes,
+                candidates_start,
+                usize::MAX,
+                |_| {},
+            ));
+        }
+        encode_record(&primes, end)
+    })
+}
+
+/// Reads a record written by [`encoded_stream`], returning the primes it contains and the number the calculation arrived to, or `None` if the reader ended before the record started.
+///
+/// Returns an error of kind `UnexpectedEof` if the reader ends in the middle of the record, or of kind `InvalidData` if the prime file it contains isn't valid.
+pub fn read_record<N: Element, R: Read>(r: &mut R) -> io::Result<Option<(Vec<N>, N)>> {
+    let mut prefix = [0; RECORD_PREFIX_LEN];
+    let mut filled = 0;
+    while filled < RECORD_PREFIX_LEN {
+        match r.read(&mut prefix[filled..]) {
+            Ok(0) if filled == 0 => return Ok(None),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let len = u64::from_le_bytes(prefix);
+    let mut payload = Vec::new();
+    r.take(len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    read_primes(&mut payload.as_slice()).map(Some)
+}
+
+/// The columns that [`write_parquet`] stores besides the primes.
+#[cfg(feature = "parquet")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParquetColumns {
+    /// Adds the `index` column, the position of every prime in the sequence written, starting from 1. If the sequence starts from 2, it's the number of primes up to it.
+    pub index: bool,
+    /// Adds the `gap` column, the difference between every prime and the previous one, which is null for the first prime.
+    pub gap: bool,
+}
+
+/// How many primes [`write_parquet`] buffers before writing them as a row group.
+#[cfg(feature = "parquet")]
+pub const PARQUET_ROW_GROUP_LEN: usize = 1 << 20;
+
+/// Writes the primes to an Apache Parquet file, in a non-nullable `prime` column of u64 and the other columns specified, so that they can be loaded directly by DataFrame libraries.
+///
+/// The primes are taken from the iterator one row group of [`PARQUET_ROW_GROUP_LEN`] at a time, and every row group is written before the next one is collected, so that a lazy iterator can be written without keeping every prime in memory.
+///
+/// Requires the `parquet` feature.
+///
+/// # Examples
+///
+/// ```
+/// use prime::generator::*;
+/// use prime::io::*;
+///
+/// let path = std::env::temp_dir().join(format!("prime_write_parquet_doctest_{}.parquet", std::process::id()));
+/// let columns = ParquetColumns { index: true, gap: true };
+/// write_parquet(&path, boxed_primes(Some(100u64)), columns).unwrap();
+/// assert_eq!(read_parquet(&path).unwrap().len(), 25);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[cfg(feature = "parquet")]
+pub fn write_parquet<I: IntoIterator<Item = u64>, P: AsRef<Path>>(
+    path: P,
+    primes: I,
+    columns: ParquetColumns,
+) -> io::Result<()> {
+    write_parquet_with(path, primes, columns, PARQUET_ROW_GROUP_LEN)
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet_with<I: IntoIterator<Item = u64>, P: AsRef<Path>>(
+    path: P,
+    primes: I,
+    columns: ParquetColumns,
+    row_group_len: usize,
+) -> io::Result<()> {
+    let mut fields = vec![Field::new("prime", DataType::UInt64, false)];
+    if columns.index {
+        fields.push(Field::new("index", DataType::UInt64, false));
+    }
+    if columns.gap {
+        fields.push(Field::new("gap", DataType::UInt64, true));
+    }
+    let schema = Arc::new(Schema::new(fields));
+    let properties = WriterProperties::builder()
+        .set_max_row_group_row_count(Some(row_group_len))
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(properties))
+        .map_err(io::Error::other)?;
+
+    let mut primes = primes.into_iter().peekable();
+    let mut index = 0;
+    let mut previous = None;
+    while primes.peek().is_some() {
+        let group: Vec<u64> = primes.by_ref().take(row_group_len).collect();
+
+        let mut arrays: Vec<ArrayRef> = vec![Arc::new(UInt64Array::from(group.clone()))];
+        if columns.index {
+            let indices = (index + 1..).take(group.len());
+            arrays.push(Arc::new(UInt64Array::from_iter_values(indices)));
+        }
+        if columns.gap {
+            let gaps = group.iter().map(|&p| previous.replace(p).map(|q| p - q));
+            arrays.push(Arc::new(UInt64Array::from_iter(gaps)));
+        }
+        previous = group.last().copied();
+        index += group.len() as u64;
+
+        let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(io::Error::other)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+        writer.flush().map_err(io::Error::other)?;
+    }
+    writer.close().map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// Reads the `prime` column of an Apache Parquet file, like the ones written by [`write_parquet`].
+///
+/// Requires the `parquet` feature.
+///
+/// # Errors
+///
+/// Besides the errors of the file, an error of kind [`io::ErrorKind::InvalidData`] is returned if the file isn't valid Parquet or doesn't have a `prime` column of u64.
+#[cfg(feature = "parquet")]
+pub fn read_parquet<P: AsRef<Path>>(path: P) -> io::Result<Vec<u64>> {
+    let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)
+        .and_then(|builder| builder.build())
+        .map_err(|e| invalid(e.to_string()))?;
+
+    let mut primes = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| invalid(e.to_string()))?;
+        let column = batch
+            .column_by_name("prime")
+            .and_then(|c| c.as_any().downcast_ref::<UInt64Array>())
+            .ok_or_else(|| invalid("missing a prime column of u64".to_string()))?;
+        primes.extend(column.values().iter());
+    }
+    Ok(primes)
+}
+
+/// A list of primes that can be serialized with serde, or saved in a portable format like JSON instead of the native bytes of [`crate::format`].
+///
+/// Requires the `serde` feature; [`PrimeList::save_json`] and [`PrimeList::load_json`] also require the `json` feature.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use prime::generator::*;
+/// use prime::io::*;
+///
+/// let path = std::env::temp_dir().join(format!("prime_list_doctest_{}.json", std::process::id()));
+/// let list = PrimeList(prime_generator(20u32, Vec::new(), 0));
+/// list.save_json(&path).unwrap();
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "[2,3,5,7,11,13,17,19]");
+/// assert_eq!(PrimeList::<u32>::load_json(&path).unwrap(), list);
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct PrimeList<N>(pub Vec<N>);
+
+#[cfg(feature = "serde")]
+impl<N> From<Vec<N>> for PrimeList<N> {
+    fn from(primes: Vec<N>) -> Self {
+        PrimeList(primes)
+    }
+}
+
+/// Keeps the errors of the file as they are, and turns the ones of the JSON (a truncated array included) into [`io::ErrorKind::InvalidData`].
+#[cfg(feature = "json")]
+fn json_error(error: serde_json::Error) -> io::Error {
+    if error.is_io() {
+        error.into()
+    } else {
+        invalid_data(error)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<N: serde::Serialize + serde::de::DeserializeOwned + Ord> PrimeList<N> {
+    /// Writes the primes to a file as a JSON array.
+    pub fn save_json<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, self).map_err(json_error)?;
+        file.flush()
+    }
+
+    /// Reads the primes from a file with a JSON array, like the ones written by [`PrimeList::save_json`].
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of the file, an error of kind [`io::ErrorKind::InvalidData`] is returned if the file isn't an array of numbers that fit in N (a truncated one included), or if they aren't in increasing order, as every function of this crate requires.
+    pub fn load_json<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let list: Self =
+            serde_json::from_reader(io::BufReader::new(File::open(path)?)).map_err(invalid_data)?;
+        if list.0.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(invalid_data("the primes aren't in increasing order"));
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn generate_to_file_test() {
+        let path = std::env::temp_dir().join(format!(
+            "prime_generate_to_file_test_{}.dat",
+            std::process::id()
+        ));
+
+        par_generate_to_file(10_000u32, &path, 1000).unwrap();
+        let (primes, arrived_to) =
+            read_primes::<u32, _>(&mut fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(primes, prime_generator(10_000, Vec::new(), 0));
+        assert_eq!(arrived_to, 10_000);
+
+        // The chunks don't need to be aligned to anything.
+        par_generate_to_file(9_999usize, &path, 7).unwrap();
+        let (primes, _) = read_primes::<usize, _>(&mut fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(primes, prime_generator(9_999, Vec::new(), 0));
+
+        par_generate_to_file(3u32, &path, 10).unwrap();
+        let (primes, _) = read_primes::<u32, _>(&mut fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(primes, vec![2]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn primes_reversed_test() {
+        let path = std::env::temp_dir().join(format!(
+            "prime_primes_reversed_test_{}.dat",
+            std::process::id()
+        ));
+
+        // More primes than a block, so that several are read.
+        let primes = prime_generator(100_000u32, Vec::new(), 0);
+        write_primes(&mut fs::File::create(&path).unwrap(), &primes, 100_000).unwrap();
+        let reversed: Vec<u32> = primes_reversed(&path).collect::<Result<_, _>>().unwrap();
+        assert!(reversed.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(reversed, primes.iter().rev().cloned().collect::<Vec<_>>());
+
+        write_primes::<u64, _>(&mut fs::File::create(&path).unwrap(), &[], 0).unwrap();
+        assert_eq!(primes_reversed::<u64, _>(&path).count(), 0);
+
+        // The width doesn't match.
+        let mut reversed = primes_reversed::<u32, _>(&path);
+        assert_eq!(
+            reversed.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(reversed.next().is_none());
+
+        fs::remove_file(&path).unwrap();
+        assert!(primes_reversed::<u32, _>(&path).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn resumable_test() {
+        let path =
+            std::env::temp_dir().join(format!("prime_resumable_test_{}.dat", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let read = || read_primes_checked::<u32, _>(&mut fs::File::open(&path).unwrap()).unwrap();
+        let expected = prime_generator(1_000_000u32, Vec::new(), 0);
+        let below = |n| {
+            expected
+                .iter()
+                .cloned()
+                .filter(|&p| p < n)
+                .collect::<Vec<_>>()
+        };
+
+        // Cancelled before starting.
+        let cancel = AtomicBool::new(true);
+        assert_eq!(
+            generate_to_file_resumable(&path, 1000u32, 100, &cancel).unwrap(),
+            0
+        );
+        assert_eq!(read(), (vec![], 0));
+
+        let cancel = AtomicBool::new(false);
+        assert_eq!(
+            generate_to_file_resumable(&path, 1000u32, 100, &cancel).unwrap(),
+            1000
+        );
+        assert_eq!(read(), (below(1000), 1000));
+
+        // A file left by a calculation cancelled halfway, after the chunk that ended at 500_123.
+        let reached = 500_123;
+        write_primes(
+            &mut fs::File::create(&path).unwrap(),
+            &below(reached),
+            reached,
+        )
+        .unwrap();
+        let stop = AtomicBool::new(true);
+        assert_eq!(
+            generate_to_file_resumable(&path, 1_000_000u32, 997, &stop).unwrap(),
+            reached
+        );
+        assert_eq!(read(), (below(reached), reached));
+
+        assert_eq!(
+            generate_to_file_resumable(&path, 1_000_000u32, 10_000, &cancel).unwrap(),
+            1_000_000
+        );
+        assert_eq!(read(), (expected, 1_000_000));
+
+        // The file can't be continued with another type.
+        let err = generate_to_file_resumable(&path, 2_000_000u64, 10_000, &cancel).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn encoded_stream_test() {
+        let expected = prime_generator(100_000u64, Vec::new(), 0);
+        for chunk_size in [1, 7, 1000, 100_000, u64::MAX] {
+            let records: Vec<Vec<u8>> = encoded_stream(100_000u64, chunk_size).collect();
+            assert_eq!(records.len() as u64, 100_000u64.div_ceil(chunk_size));
+
+            let bytes = records.concat();
+            let mut reader = bytes.as_slice();
+            let mut primes = Vec::new();
+            let mut previous_end = 0;
+            while let Some((mut chunk, end)) = read_record::<u64, _>(&mut reader).unwrap() {
+                assert!(chunk.iter().all(|&p| p >= previous_end && p < end));
+                assert_eq!(end, previous_end.saturating_add(chunk_size).min(100_000));
+                primes.append(&mut chunk);
+                previous_end = end;
+            }
+            assert_eq!(primes, expected, "{}", chunk_size);
+        }
+
+        assert_eq!(encoded_stream(0u32, 10).count(), 0);
+        let bytes: Vec<u8> = encoded_stream(3u8, 2).flatten().collect();
+        let mut reader = bytes.as_slice();
+        assert_eq!(read_record(&mut reader).unwrap(), Some((vec![], 2u8)));
+        assert_eq!(read_record(&mut reader).unwrap(), Some((vec![2u8], 3)));
+    }
+
+    #[test]
+    fn read_record_test() {
+        let record: Vec<u8> = encoded_stream(100u32, 100).flatten().collect();
+        for len in 1..record.len() {
+            let err = read_record::<u32, _>(&mut &record[..len]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof, "{}", len);
+        }
+        let err = read_record::<u64, _>(&mut record.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(read_record::<u32, _>(&mut io::empty()).unwrap(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn parquet_test() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let path =
+            std::env::temp_dir().join(format!("prime_parquet_test_{}.parquet", std::process::id()));
+        let primes = prime_generator(1_299_710u64, Vec::new(), 0);
+        assert_eq!(primes.len(), 100_000);
+
+        write_parquet(&path, primes.iter().copied(), ParquetColumns::default()).unwrap();
+        assert_eq!(read_parquet(&path).unwrap(), primes);
+
+        // Every column is readable by arrow, in row groups of the length specified.
+        let columns = ParquetColumns {
+            index: true,
+            gap: true,
+        };
+        write_parquet_with(&path, primes.iter().copied(), columns, 30_000).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 4);
+        let names: Vec<_> = builder
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, ["prime", "index", "gap"]);
+
+        let (mut indices, mut gaps) = (Vec::new(), Vec::new());
+        for batch in builder.build().unwrap() {
+            let batch = batch.unwrap();
+            let column = |name| {
+                batch
+                    .column_by_name(name)
+                    .unwrap()
+                    .as_any()
+                    .downcast_ref::<UInt64Array>()
+                    .unwrap()
+                    .clone()
+            };
+            indices.extend(column("index").values().iter().copied());
+            gaps.extend(column("gap").iter());
+        }
+        assert_eq!(indices, (1..=100_000).collect::<Vec<u64>>());
+        assert_eq!(gaps[0], None);
+        assert!(gaps[1..]
+            .iter()
+            .zip(primes.windows(2))
+            .all(|(&gap, w)| gap == Some(w[1] - w[0])));
+        assert_eq!(read_parquet(&path).unwrap(), primes);
+
+        write_parquet(&path, [], columns).unwrap();
+        assert_eq!(read_parquet(&path).unwrap(), Vec::<u64>::new());
+
+        fs::write(&path, b"not parquet").unwrap();
+        assert_eq!(
+            read_parquet(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn prime_list_test() {
+        let path =
+            std::env::temp_dir().join(format!("prime_list_test_{}.json", std::process::id()));
+        let list = PrimeList::from(prime_generator(100_000u64, Vec::new(), 0));
+        list.save_json(&path).unwrap();
+        assert_eq!(PrimeList::<u64>::load_json(&path).unwrap(), list);
+
+        PrimeList(Vec::<u8>::new()).save_json(&path).unwrap();
+        assert_eq!(
+            PrimeList::<u8>::load_json(&path).unwrap().0,
+            Vec::<u8>::new()
+        );
+
+        for invalid in ["[2, 3, 3]", "[5, 3]", "[2, 300]", "{}", "[2, 3", "[2, -3]"] {
+            fs::write(&path, invalid).unwrap();
+            let error = PrimeList::<u8>::load_json(&path).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", invalid);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
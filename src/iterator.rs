@@ -0,0 +1,132 @@
+use crate::calculator::*;
+use num::integer::Roots;
+use num::CheckedAdd;
+use std::iter::FusedIterator;
+
+/// An iterator over the primes in order, calculated lazily one at a time when they are requested.
+///
+/// Every prime returned is kept in a list that is used to check the following candidates with [`is_prime`], so the iterator can be stopped at any point and resumed later with [`Primes::resume`], and cloning it takes a snapshot of its state.
+///
+/// After 2 and 3, the candidates advance by two. When the next candidate would overflow N the iteration ends, so the last prime returned is the greatest one that fits in N.
+///
+/// # Examples
+///
+/// ```
+/// use prime::iterator::*;
+///
+/// let mut primes = Primes::<u32>::new();
+/// assert_eq!(primes.by_ref().take(5).collect::<Vec<_>>(), vec![2, 3, 5, 7, 11]);
+///
+/// let snapshot = primes.clone();
+/// assert_eq!(primes.next(), Some(13));
+/// assert_eq!(snapshot.take(2).collect::<Vec<_>>(), vec![13, 17]);
+///
+/// // Every u8 prime, up to 251.
+/// assert_eq!(Primes::<u8>::new().last(), Some(251));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Primes<N> {
+    known_primes: Vec<N>,
+    next: Option<N>,
+}
+
+impl<N: Roots + CheckedAdd + Copy> Primes<N> {
+    /// Creates an iterator that starts from 2.
+    pub fn new() -> Self {
+        Primes {
+            known_primes: Vec::new(),
+            next: Some(N::one() + N::one()),
+        }
+    }
+
+    /// Creates an iterator that continues after the last prime of the list, like one that has already returned them.
+    ///
+    /// The list must be ordered and contain every prime up to its last element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use prime::iterator::*;
+    ///
+    /// let mut primes = Primes::resume(vec![2u32, 3, 5, 7]);
+    /// assert_eq!(primes.next(), Some(11));
+    /// ```
+    pub fn resume(known_primes: Vec<N>) -> Self {
+        let two = N::one() + N::one();
+        let next = match known_primes.last() {
+            None => Some(two),
+            Some(&p) if p == two => Some(two + N::one()),
+            Some(&p) => p.checked_add(&two),
+        };
+        Primes { known_primes, next }
+    }
+
+    /// The primes returned so far, or sent to [`Primes::resume`].
+    pub fn known_primes(&self) -> &[N] {
+        &self.known_primes
+    }
+
+    /// Consumes the iterator and returns the primes returned so far.
+    pub fn into_known_primes(self) -> Vec<N> {
+        self.known_primes
+    }
+}
+
+impl<N: Roots + CheckedAdd + Copy> Default for Primes<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<N: Roots + CheckedAdd + Copy> Iterator for Primes<N> {
+    type Item = N;
+
+    fn next(&mut self) -> Option<N> {
+        let two = N::one() + N::one();
+        loop {
+            let n = self.next?;
+            self.next = n.checked_add(&if n == two { N::one() } else { two });
+            if is_prime(n, &self.known_primes) {
+                self.known_primes.push(n);
+                return Some(n);
+            }
+        }
+    }
+}
+
+impl<N: Roots + CheckedAdd + Copy> FusedIterator for Primes<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::*;
+
+    #[test]
+    fn primes_test() {
+        let expected = prime_generator(100_000u32, Vec::new(), 0);
+        let primes: Vec<u32> = Primes::new().take_while(|&p| p < 100_000).collect();
+        assert_eq!(primes, expected);
+
+        // Stopping and resuming gives the same primes.
+        let mut iter = Primes::<u64>::new();
+        let mut primes: Vec<u64> = iter.by_ref().take(1000).collect();
+        let mut resumed = Primes::resume(iter.into_known_primes());
+        primes.extend(resumed.by_ref().take(1000));
+        assert_eq!(primes, resumed.known_primes());
+        assert_eq!(
+            primes[..],
+            expected.iter().map(|&p| p as u64).collect::<Vec<_>>()[..2000]
+        );
+
+        assert_eq!(Primes::resume(Vec::<u32>::new()).next(), Some(2));
+        assert_eq!(Primes::resume(vec![2u32]).next(), Some(3));
+        assert_eq!(Primes::resume(vec![2u32, 3]).next(), Some(5));
+
+        // The iteration ends before overflowing.
+        let u8_primes: Vec<u8> = Primes::new().collect();
+        assert_eq!(u8_primes.len(), 54);
+        let mut i8_primes = Primes::<i8>::new();
+        assert_eq!(i8_primes.by_ref().last(), Some(127));
+        assert_eq!(i8_primes.next(), None);
+    }
+}
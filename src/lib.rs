@@ -1,2 +1,19 @@
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+
+pub mod analysis;
+pub mod big;
+pub mod bloom;
+pub mod cache;
 pub mod calculator;
+pub mod constellation;
+pub mod display;
+pub mod factorization;
+pub mod format;
 pub mod generator;
+pub mod io;
+pub mod iterator;
+#[cfg(all(test, feature = "proptest"))]
+mod properties;
+pub mod sieve;
+#[cfg(feature = "async")]
+pub mod stream;
@@ -0,0 +1,57 @@
+//! Invariants that every generator must respect, checked on random inputs.
+
+use crate::calculator::*;
+use crate::generator::*;
+use proptest::prelude::*;
+
+/// Values of until that are big enough to need a setup before the parallel generators start.
+fn until() -> impl Strategy<Value = u32> {
+    3u32..50_000
+}
+
+/// An ordered list with every prime less than a random number, as a calculation interrupted there would leave it.
+fn known_primes() -> impl Strategy<Value = Vec<u32>> {
+    (3u32..2_000).prop_map(|n| prime_generator(n, Vec::new(), 0))
+}
+
+proptest! {
+    #[test]
+    fn every_element_is_prime(until in until()) {
+        let primes = prime_generator(until, Vec::new(), 0);
+        for &p in &primes {
+            prop_assert!(ignorant_is_prime(p, &[]), "{} isn't prime", p);
+        }
+    }
+
+    #[test]
+    fn strictly_increasing(until in until()) {
+        let primes = prime_generator(until, Vec::new(), 0);
+        prop_assert!(primes.windows(2).all(|w| w[0] < w[1]));
+        prop_assert!(primes.iter().all(|&p| p < until));
+    }
+
+    #[test]
+    fn counts_every_prime(until in 3u32..5_000) {
+        let primes = prime_generator(until, Vec::new(), 0);
+        let count = (0..until as u64).filter(|&n| deterministic_is_prime(n)).count();
+        prop_assert_eq!(primes.len(), count);
+    }
+
+    #[test]
+    fn sequential_and_parallel_agree(until in until(), known_primes in known_primes()) {
+        prop_assume!(known_primes.last().is_none_or(|&p| p < until));
+        prop_assert_eq!(
+            prime_generator(until, known_primes.clone(), 0),
+            par_prime_generator(until, known_primes, 0)
+        );
+    }
+
+    #[test]
+    fn known_primes_only_save_work(until in until(), known_primes in known_primes()) {
+        prop_assume!(known_primes.last().is_none_or(|&p| p < until));
+        prop_assert_eq!(
+            prime_generator(until, known_primes, 0),
+            prime_generator(until, Vec::new(), 0)
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use num::FromPrimitive;
+use std::cmp;
+use std::iter;
+
+/// Returns how many u64 are needed to hold one bit for every number less than until.
+///
+/// # Examples
+///
+/// ```
+/// use prime::sieve::*;
+///
+/// assert_eq!(bits_required(0), 0);
+/// assert_eq!(bits_required(64), 1);
+/// assert_eq!(bits_required(65), 2);
+/// ```
+pub fn bits_required(until: usize) -> usize {
+    until.div_ceil(64)
+}
+
+/// Marks the numbers less than until in a bit buffer owned by the caller with the Sieve of Eratosthenes, so that the buffer can be reused between calls.
+///
+/// After the call, the bit `n % 64` of `bits[n / 64]` is set if and only if n is prime. Only the first [`bits_required`] words are written to.
+///
+/// # Examples
+///
+/// ```
+/// use prime::sieve::*;
+///
+/// let mut bits = vec![0; bits_required(20)];
+/// sieve_into_bits(20, &mut bits);
+/// assert_eq!(bits[0], 1 << 2 | 1 << 3 | 1 << 5 | 1 << 7 | 1 << 11 | 1 << 13 | 1 << 17 | 1 << 19);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if the buffer is shorter than [`bits_required`].
+pub fn sieve_into_bits(until: usize, bits: &mut [u64]) {
+    let words = bits_required(until);
+    assert!(
+        bits.len() >= words,
+        "the buffer has {} words, but {} are required",
+        bits.len(),
+        words
+    );
+    let bits = &mut bits[..words];
+    if words == 0 {
+        return;
+    }
+
+    bits.fill(u64::MAX);
+    bits[0] &= !0b11;
+    if !until.is_multiple_of(64) {
+        bits[words - 1] &= (1 << (until % 64)) - 1;
+    }
+
+    let mut p = 2;
+    while p * p < until {
+        if bits[p / 64] & (1 << (p % 64)) != 0 {
+            mark_multiples(bits, p, until);
+        }
+        p += 1;
+    }
+}
+
+/// The default size in bytes of the window of [`segmented_sieve`], about the size of the L2 cache of a core.
+///
+/// The window is crossed out once for every base prime, so it's fastest when it stays in a cache close to the core. A smaller window is crossed out more times for each prime, though, so the window shouldn't be much smaller either.
+pub const DEFAULT_SEGMENT_BYTES: usize = 256 * 1024;
+
+/// Finds the primes less than until with a segmented Sieve of Eratosthenes, calling `found` with the primes of every segment in order.
+///
+/// Only odd numbers are stored, one bit each, in a window of segment_bytes bytes (rounded down to a multiple of 8, but at least 8) that is reused for every segment. Besides the window, only the primes up to the square root of until are kept in memory, so until can be much greater than the memory available.
+///
+/// # Examples
+///
+/// ```
+/// use prime::sieve::*;
+///
+/// let mut count = 0;
+/// segmented_sieve_map(1_000_000, DEFAULT_SEGMENT_BYTES, |primes| count += primes.len());
+/// assert_eq!(count, 78498);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if segment_bytes is 0.
+pub fn segmented_sieve_map<F: FnMut(&[u64])>(until: u64, segment_bytes: usize, mut found: F) {
+    assert!(segment_bytes != 0, "the segment can't be empty");
+    if until <= 2 {
+        return;
+    }
+    found(&[2]);
+
+    // The odd primes up to the square root of until, with the next odd multiple of each to cross out.
+    let sqr = until.isqrt() as usize + 1;
+    let mut base = vec![0; bits_required(sqr)];
+    sieve_into_bits(sqr, &mut base);
+    let mut crossing: Vec<(u64, u64)> = (3..sqr)
+        .filter(|&p| base[p / 64] & (1 << (p % 64)) != 0)
+        .map(|p| (p as u64, (p * p) as u64))
+        .collect();
+
+    let words = cmp::max(segment_bytes / 8, 1);
+    let mut window = vec![0u64; words];
+    let span = words as u64 * 128;
+    let mut primes = Vec::new();
+    // The bit i of the window is the odd number low + 2i + 1.
+    let mut low = 0;
+    while low < until {
+        let high = cmp::min(low.saturating_add(span), until);
+        window.fill(u64::MAX);
+        for (p, next) in crossing.iter_mut() {
+            while *next < high {
+                let i = (*next - low) / 2;
+                window[(i / 64) as usize] &= !(1 << (i % 64));
+                *next += 2 * *p;
+            }
+        }
+
+        primes.clear();
+        for (w, &word) in window.iter().enumerate() {
+            let mut word = word;
+            while word != 0 {
+                let n = low + 2 * (w as u64 * 64 + word.trailing_zeros() as u64) + 1;
+                if n >= high {
+                    break;
+                }
+                primes.push(n);
+                word &= word - 1;
+            }
+        }
+        // 1 isn't prime.
+        if low == 0 {
+            primes.remove(0);
+        }
+        found(&primes);
+        low = high;
+    }
+}
+
+/// Returns every prime less than until, found with [`segmented_sieve_map`].
+///
+/// # Examples
+///
+/// ```
+/// use prime::sieve::*;
+///
+/// assert_eq!(segmented_sieve(20, DEFAULT_SEGMENT_BYTES), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+///
+/// # Panics
+///
+/// The function panics if segment_bytes is 0.
+pub fn segmented_sieve(until: u64, segment_bytes: usize) -> Vec<u64> {
+    let mut primes = Vec::new();
+    segmented_sieve_map(until, segment_bytes, |chunk| {
+        primes.extend_from_slice(chunk)
+    });
+    primes
+}
+
+/// The primes less than this are marked with [`mark_multiples_simd`] when the `simd` feature is enabled: their multiples are dense enough to clear at least one bit in every word.
+#[cfg(feature = "simd")]
+const SIMD_STRIDE_LIMIT: usize = 64;
+
+/// Clears the bits of the multiples of p from p * p to until.
+fn mark_multiples(bits: &mut [u64], p: usize, until: usize) {
+    #[cfg(feature = "simd")]
+    if p < SIMD_STRIDE_LIMIT {
+        return mark_multiples_simd(bits, p, until);
+    }
+    mark_multiples_scalar(bits, p, until);
+}
+
+/// Clears the bits of the multiples of p from p * p to until, one at a time.
+fn mark_multiples_scalar(bits: &mut [u64], p: usize, until: usize) {
+    (p * p..until)
+        .step_by(p)
+        .for_each(|n| bits[n / 64] &= !(1 << (n % 64)));
+}
+
+/// Clears the bits of the multiples of p from p * p to until, four words at a time.
+///
+/// The multiples of p repeat every p words, so p vectors of masks cover every alignment of the stride.
+/// The word containing p * p is handled apart, since p and its multiples below p * p must keep their bits.
+/// The bits after until are cleared too, but they are already 0.
+///
+/// Only [`sieve_into_bits`] uses it: the window of [`segmented_sieve_map`], and so [`BitSieve`], stores only the odd numbers from an offset that changes with every segment, so its multiples don't repeat with the same masks and are still crossed out one at a time.
+#[cfg(feature = "simd")]
+fn mark_multiples_simd(bits: &mut [u64], p: usize, until: usize) {
+    use std::simd::u64x4;
+
+    let first_word = p * p / 64;
+    if first_word >= bits.len() {
+        return;
+    }
+    mark_multiples_scalar(
+        &mut bits[..=first_word],
+        p,
+        until.min((first_word + 1) * 64),
+    );
+
+    let offset = (first_word + 1) * 64;
+    let word_mask = |word: usize| {
+        let start = offset + word * 64;
+        let mut mask = u64::MAX;
+        let mut bit = (p - start % p) % p;
+        while bit < 64 {
+            mask &= !(1 << bit);
+            bit += p;
+        }
+        mask
+    };
+    let pattern: Vec<u64x4> = (0..p)
+        .map(|i| u64x4::from_array(std::array::from_fn(|lane| word_mask(i * 4 + lane))))
+        .collect();
+
+    let (chunks, tail) = bits[first_word + 1..].as_chunks_mut::<4>();
+    let tail_start = chunks.len() * 4;
+    for (chunk, mask) in chunks.iter_mut().zip(pattern.iter().cycle()) {
+        *chunk = (u64x4::from_array(*chunk) & mask).to_array();
+    }
+    for (i, word) in tail.iter_mut().enumerate() {
+        *word &= word_mask(tail_start + i);
+    }
+}
+
+/// The primes less than a number, stored as a bitset over the odd numbers: a bit for every odd number, set if it's prime, and 2 implied.
+///
+/// It takes 1 bit for every 2 numbers, half of a bitset over every number: up to 10^9 it's about 60 MB, while the 50847534 primes take 200 MB in a `Vec<u32>`.
+///
+/// # Examples
+///
+/// ```
+/// use prime::sieve::*;
+///
+/// let sieve = BitSieve::new(20);
+/// assert!(sieve.contains(19));
+/// assert!(!sieve.contains(9));
+/// assert_eq!(sieve.iter().collect::<Vec<_>>(), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// assert_eq!(sieve.into_vec::<u32>(), vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitSieve {
+    /// The bit i % 64 of bits[i / 64] is the odd number 2i + 1.
+    bits: Vec<u64>,
+    until: u64,
+}
+
+impl BitSieve {
+    /// Finds the primes less than until with [`segmented_sieve_map`], and sets their bits.
+    pub fn new(until: u64) -> Self {
+        let mut bits = vec![0; until.div_ceil(128) as usize];
+        segmented_sieve_map(until, DEFAULT_SEGMENT_BYTES, |primes| {
+            for &p in primes.iter().filter(|&&p| p != 2) {
+                let i = p / 2;
+                bits[(i / 64) as usize] |= 1 << (i % 64);
+            }
+        });
+        BitSieve { bits, until }
+    }
+
+    /// The number the primes are less than.
+    pub fn until(&self) -> u64 {
+        self.until
+    }
+
+    /// Checks if n is one of the primes, which is false for every number greater than or equal to until.
+    pub fn contains(&self, n: u64) -> bool {
+        if n >= self.until {
+            return false;
+        }
+        if n.is_multiple_of(2) {
+            return n == 2;
+        }
+        let i = n / 2;
+        self.bits[(i / 64) as usize] & (1 << (i % 64)) != 0
+    }
+
+    /// Returns how many primes there are.
+    pub fn len(&self) -> usize {
+        let odd: usize = self.bits.iter().map(|w| w.count_ones() as usize).sum();
+        odd + (self.until > 2) as usize
+    }
+
+    /// Checks if there are no primes, which happens when until is at most 2.
+    pub fn is_empty(&self) -> bool {
+        self.until <= 2
+    }
+
+    /// Returns the primes in order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let two = (self.until > 2).then_some(2);
+        let odd = self.bits.iter().enumerate().flat_map(|(w, &word)| {
+            let mut word = word;
+            iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let i = w as u64 * 64 + word.trailing_zeros() as u64;
+                word &= word - 1;
+                Some(2 * i + 1)
+            })
+        });
+        two.into_iter().chain(odd)
+    }
+
+    /// Converts the primes to a Vec, so that it can be passed to the functions that take a list of known primes.
+    ///
+    /// # Panics
+    ///
+    /// The function panics if a prime doesn't fit in N.
+    pub fn into_vec<N: FromPrimitive>(self) -> Vec<N> {
+        let mut primes = Vec::with_capacity(self.len());
+        primes.extend(self.iter().map(|p| N::from_u64(p).unwrap()));
+        primes
+    }
+
+    /// The memory taken by the bitset, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bits.len() * 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::*;
+
+    fn set_bits(bits: &[u64]) -> Vec<usize> {
+        (0..bits.len() * 64)
+            .filter(|n| bits[n / 64] & (1 << (n % 64)) != 0)
+            .collect()
+    }
+
+    #[test]
+    fn sieve_into_bits_test() {
+        for until in [0, 1, 2, 3, 63, 64, 65, 128, 1000, 100_000] {
+            let mut bits = vec![0; bits_required(until)];
+            sieve_into_bits(until, &mut bits);
+            let expected: Vec<usize> = prime_generator(until.max(3), Vec::new(), 0)
+                .into_iter()
+                .filter(|&p| p < until)
+                .collect();
+            assert_eq!(set_bits(&bits), expected, "{}", until);
+        }
+
+        // The buffer can be reused, and the words after the required ones are untouched.
+        let mut bits = vec![0; 4];
+        sieve_into_bits(128, &mut bits);
+        sieve_into_bits(70, &mut bits);
+        assert_eq!(bits[2..], [0, 0]);
+        assert_eq!(set_bits(&bits[..2]).last(), Some(&67));
+    }
+
+    #[test]
+    #[should_panic]
+    fn sieve_into_bits_panic() {
+        sieve_into_bits(65, &mut [0]);
+    }
+
+    #[test]
+    fn segmented_sieve_test() {
+        let expected = prime_generator(300_000u64, Vec::new(), 0);
+        // Down to a single word, so that the primes cross many segments.
+        for segment_bytes in [1, 8, 16, 24, 1000, DEFAULT_SEGMENT_BYTES] {
+            for until in [0, 1, 2, 3, 4, 127, 128, 129, 255, 256, 257, 10_000, 300_000] {
+                let below = expected.partition_point(|&p| p < until);
+                assert_eq!(
+                    segmented_sieve(until, segment_bytes),
+                    expected[..below],
+                    "until {}, segment_bytes {}",
+                    until,
+                    segment_bytes
+                );
+            }
+        }
+
+        // Every segment but the last one spans 128 numbers for every word.
+        let mut segments = Vec::new();
+        segmented_sieve_map(1000, 8, |primes| segments.push(primes.to_vec()));
+        assert_eq!(segments.len(), 1 + 1000usize.div_ceil(128));
+        assert_eq!(segments[0], vec![2]);
+        assert!(segments[2].iter().all(|&p| (128..256).contains(&p)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn segmented_sieve_panic() {
+        segmented_sieve(100, 0);
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn mark_multiples_simd_test() {
+        for until in [0, 1, 5, 64, 100, 255, 256, 257, 1000, 4096, 100_003] {
+            for p in 2..SIMD_STRIDE_LIMIT {
+                if p * p >= until {
+                    break;
+                }
+                let mut scalar = vec![u64::MAX; bits_required(until)];
+                let last = scalar.len() - 1;
+                if !until.is_multiple_of(64) {
+                    scalar[last] &= (1 << (until % 64)) - 1;
+                }
+                let mut simd = scalar.clone();
+                mark_multiples_scalar(&mut scalar, p, until);
+                mark_multiples_simd(&mut simd, p, until);
+                assert_eq!(scalar, simd, "until {}, p {}", until, p);
+            }
+        }
+    }
+
+    #[test]
+    fn bit_sieve_test() {
+        for until in (0..300).chain([127, 128, 129, 100_000]) {
+            let expected = prime_generator(until, Vec::new(), 0);
+            let sieve = BitSieve::new(until);
+            assert_eq!(sieve.until(), until);
+            assert_eq!(sieve.len(), expected.len());
+            assert_eq!(sieve.is_empty(), expected.is_empty());
+            assert_eq!(sieve.iter().collect::<Vec<_>>(), expected);
+            for n in 0..until + 10 {
+                assert_eq!(sieve.contains(n), expected.binary_search(&n).is_ok());
+            }
+            assert_eq!(sieve.into_vec::<u64>(), expected);
+        }
+
+        let sieve = BitSieve::new(1_000_000);
+        // Less than a quarter of a Vec<u32>.
+        assert!(sieve.size_in_bytes() < sieve.len() * std::mem::size_of::<u32>() / 4);
+        let primes: Vec<u32> = sieve.into_vec();
+        assert_eq!(primes.len(), 78498);
+        assert!(crate::calculator::is_prime(1_000_003, &primes));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::generator::*;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, Stream};
+use num::integer::Roots;
+use num::{CheckedAdd, FromPrimitive, ToPrimitive};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll};
+use std::thread;
+
+/// A [`Stream`] of every prime less than until, in order.
+///
+/// The primes are calculated on a separate thread and sent over a bounded channel, so polling the stream never blocks. When the channel is full, the calculation waits for the primes to be consumed, and it stops when the stream is dropped.
+///
+/// # Examples
+///
+/// ```
+/// use futures::executor::block_on;
+/// use futures::StreamExt;
+/// use prime::stream::*;
+///
+/// let primes: Vec<u32> = block_on(PrimeStream::new(20, Vec::new(), 4).collect());
+/// assert_eq!(primes, vec![2, 3, 5, 7, 11, 13, 17, 19]);
+/// ```
+pub struct PrimeStream<N> {
+    receiver: mpsc::Receiver<N>,
+}
+
+impl<N: Roots + FromPrimitive + ToPrimitive + Copy + CheckedAdd + Send + 'static> PrimeStream<N> {
+    /// Starts calculating the primes less than until, keeping at most capacity of them waiting to be consumed.
+    ///
+    /// The primes of the Vec sent less than until are yielded first. The Vec must be ordered. If you send an empty Vec, 2 and 3 will be added automatically.
+    pub fn new(until: N, known_primes: Vec<N>, capacity: usize) -> Self {
+        let (mut sender, receiver) = mpsc::channel(capacity);
+        thread::spawn(move || {
+            let cancel = AtomicBool::new(false);
+            let mut send = |n: N| {
+                if !cancel.load(Ordering::Relaxed) && block_on(sender.send(n)).is_err() {
+                    // The stream was dropped.
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            };
+            known_primes
+                .iter()
+                .take_while(|&&p| p < until)
+                .for_each(|&p| send(p));
+            let _ = cancellable_generator_map(
+                until,
+                known_primes,
+                N::zero(),
+                &cancel,
+                ReserveStrategy::Geometric,
+                send,
+            );
+        });
+        PrimeStream { receiver }
+    }
+}
+
+impl<N> Stream for PrimeStream<N> {
+    type Item = N;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<N>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn stream_test() {
+        let expected = prime_generator(10_000u32, Vec::new(), 0);
+        let primes: Vec<u32> = block_on(PrimeStream::new(10_000, Vec::new(), 16).collect());
+        assert_eq!(primes, expected);
+
+        let primes: Vec<u32> =
+            block_on(PrimeStream::new(10_000, expected[..100].to_vec(), 1).collect());
+        assert_eq!(primes, expected);
+
+        // Dropping the stream early stops the calculation.
+        let primes: Vec<u32> =
+            block_on(PrimeStream::new(u32::MAX, Vec::new(), 1).take(10).collect());
+        assert_eq!(primes, expected[..10]);
+    }
+}
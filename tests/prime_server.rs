@@ -0,0 +1,48 @@
+//! Runs the server and client examples against each other.
+
+#[allow(dead_code)]
+#[path = "../examples/prime_client.rs"]
+mod prime_client;
+#[allow(dead_code)]
+#[path = "../examples/prime_server.rs"]
+mod prime_server;
+
+use prime::generator::prime_generator;
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+#[test]
+fn server_and_client_test() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = [0, 2, 3, 1000, 2_500_000];
+
+    let server = thread::spawn(move || {
+        listener
+            .incoming()
+            .take(requests.len() + 1)
+            .map(|stream| prime_server::handle_client(stream.unwrap()))
+            .collect::<Vec<_>>()
+    });
+
+    for until in requests {
+        assert_eq!(
+            prime_client::request_primes(address, until).unwrap(),
+            prime_generator(until, Vec::new(), 0),
+            "{}",
+            until
+        );
+    }
+    // The server refuses a request that isn't a number.
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(b"many\n").unwrap();
+    drop(stream);
+
+    let results = server.join().unwrap();
+    assert!(results[..requests.len()].iter().all(|r| r.is_ok()));
+    assert_eq!(
+        results[requests.len()].as_ref().unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+}